@@ -0,0 +1,123 @@
+// Aggregates benchmark results written with `lab2 --bench --bench-output <file>` into speedup
+// and efficiency per number of workers, relative to the run with the fewest workers.
+//
+// usage: speedup <results.csv|results.json>...
+
+use serde::Deserialize;
+use std::{collections::BTreeMap, fs, process};
+
+#[derive(Deserialize)]
+struct Row {
+    workers: i32,
+    elapsed: f64,
+}
+
+fn read_rows(path: &str) -> Result<Vec<Row>, String> {
+    let content: String = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+
+    if path.ends_with(".json") {
+        return serde_json::from_str(&content).map_err(|e| format!("{}: {}", path, e));
+    }
+
+    let mut lines = content.lines();
+    let header: Vec<&str> = lines
+        .next()
+        .ok_or_else(|| format!("{}: empty file", path))?
+        .split(',')
+        .collect();
+
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|x| *x == name)
+            .ok_or_else(|| format!("{}: missing column {}", path, name))
+    };
+    let (workers, elapsed) = (column("workers")?, column("elapsed")?);
+
+    let mut rows: Vec<Row> = vec![];
+
+    for (i, line) in lines.enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let invalid = || format!("{}:{}: invalid row", path, i + 2);
+        let fields: Vec<&str> = line.split(',').collect();
+
+        rows.push(Row {
+            workers: fields
+                .get(workers)
+                .ok_or_else(invalid)?
+                .parse()
+                .map_err(|_| invalid())?,
+            elapsed: fields
+                .get(elapsed)
+                .ok_or_else(invalid)?
+                .parse()
+                .map_err(|_| invalid())?,
+        });
+    }
+
+    return Ok(rows);
+}
+
+fn main() {
+    let paths: Vec<String> = std::env::args().skip(1).collect();
+
+    if paths.is_empty() {
+        eprintln!("usage: speedup <results.csv|results.json>...");
+        process::exit(1);
+    }
+
+    // Total suite time per run, grouped by worker count. Repeated runs are averaged.
+    let mut runs: BTreeMap<i32, Vec<f64>> = BTreeMap::new();
+
+    for path in paths.iter() {
+        let rows: Vec<Row> = read_rows(path).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1);
+        });
+
+        let Some(first) = rows.first() else {
+            eprintln!("{}: no results", path);
+            process::exit(1);
+        };
+
+        if rows.iter().any(|x| x.workers != first.workers) {
+            eprintln!("{}: results from different worker counts", path);
+            process::exit(1);
+        }
+
+        // A single-rank Lazy SMP run has no workers to scale against
+        if first.workers < 1 {
+            eprintln!("{}: results without workers", path);
+            process::exit(1);
+        }
+
+        runs.entry(first.workers)
+            .or_default()
+            .push(rows.iter().map(|x| x.elapsed).sum());
+    }
+
+    let times: Vec<(i32, f64)> = runs
+        .iter()
+        .map(|(workers, x)| (*workers, x.iter().sum::<f64>() / x.len() as f64))
+        .collect();
+    let (base_workers, base_time) = times[0];
+
+    println!("workers,time,ideal_speedup,speedup,efficiency");
+
+    for (workers, time) in times {
+        let ideal: f64 = workers as f64 / base_workers as f64;
+        let speedup: f64 = base_time / time;
+
+        println!(
+            "{},{:.6},{:.3},{:.3},{:.3}",
+            workers,
+            time,
+            ideal,
+            speedup,
+            speedup / ideal
+        );
+    }
+}
@@ -1,12 +1,17 @@
 use super::token::TokenColor;
+use crate::game::game::Game;
 use serde::{Deserialize, Serialize};
-use std::fmt;
+use std::{error, fmt};
 
 const DEFAULT_ROWS: usize = 6;
 const DEFAULT_COLUMNS: usize = 7;
+// Pops keep a PopOut board from filling up, a game this long is a draw.
+pub const POPOUT_MAX_PLIES: usize = 200;
+// Row and column steps of horizontal, vertical and both diagonal lines.
+const DIRECTIONS: [(isize, isize); 4] = [(0, 1), (1, 0), (1, 1), (1, -1)];
 
 #[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
-enum Field {
+pub enum Field {
     Empty,
     Token(TokenColor),
 }
@@ -23,60 +28,253 @@ impl fmt::Display for Field {
     }
 }
 
+impl Field {
+    // Tokens that are part of a winning line.
+    pub fn highlighted(self: &Self) -> &'static str {
+        return match self {
+            Field::Empty => "⚪",
+            Field::Token(TokenColor::Red) => "🟥",
+            Field::Token(TokenColor::Yellow) => "🟨",
+        };
+    }
+}
+
 #[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum GameStatus {
     InProgress,
     Finished(TokenColor),
 }
 
-#[derive(Debug, Clone)]
-pub struct MoveError {
-    message: String,
+// Standard Connect Four, or PopOut, where a player may instead take one of its own tokens from
+// the bottom of a column. A full board is a draw in both, see Board::is_draw for the other
+// draws of PopOut.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Variant {
+    #[default]
+    Standard,
+    PopOut,
+}
+
+impl Variant {
+    pub fn parse(input: &str) -> Option<Variant> {
+        return match input {
+            "standard" => Some(Variant::Standard),
+            "popout" => Some(Variant::PopOut),
+            _ => None,
+        };
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Move {
+    Drop(usize),
+    // The tokens above the popped one fall down one row.
+    Pop(usize),
+}
+
+impl Move {
+    // Drops are numbered by their column, pops follow after the last column.
+    pub fn index(self: &Self, columns: usize) -> usize {
+        return match self {
+            Move::Drop(column) => *column,
+            Move::Pop(column) => columns + column,
+        };
+    }
+
+    pub fn from_index(index: usize, columns: usize) -> Move {
+        if index < columns {
+            return Move::Drop(index);
+        }
+
+        return Move::Pop(index - columns);
+    }
+}
+
+// "3" for a drop, "p3" for a pop, the way moves are entered.
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Move::Drop(column) => write!(f, "{}", column),
+            Move::Pop(column) => write!(f, "p{}", column),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveError {
+    // The column is already full.
+    IllegalMove(usize),
+    ColumnOutOfRange(usize),
+    // Nothing to take back from the column.
+    EmptyColumn(usize),
+    // The bottom token of the column is not the player's, or the variant has no pops.
+    IllegalPop(usize),
+    // A pop is taken back that was not the last move.
+    NotPopped(usize),
+    // The same for games played on single cells instead of columns.
+    Occupied(usize),
+    CellOutOfRange(usize),
+    EmptyCell(usize),
 }
 
 impl fmt::Display for MoveError {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        write!(f, "{}", self.message)
+        match self {
+            MoveError::IllegalMove(column) => write!(f, "illegal move, column {} is full", column),
+            MoveError::ColumnOutOfRange(column) => {
+                write!(f, "column index {} out of range", column)
+            }
+            MoveError::EmptyColumn(column) => write!(f, "column {} is empty", column),
+            MoveError::IllegalPop(column) => {
+                write!(
+                    f,
+                    "illegal pop, column {} has no own token at the bottom",
+                    column
+                )
+            }
+            MoveError::NotPopped(column) => {
+                write!(f, "the last move was not a pop from column {}", column)
+            }
+            MoveError::Occupied(cell) => write!(f, "illegal move, cell {} is taken", cell),
+            MoveError::CellOutOfRange(cell) => write!(f, "cell index {} out of range", cell),
+            MoveError::EmptyCell(cell) => write!(f, "cell {} is empty", cell),
+        }
     }
 }
 
+impl error::Error for MoveError {}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Board {
     pub rows: usize,
     pub columns: usize,
     fields: Vec<Vec<Field>>,
     pub columns_full: Vec<bool>,
+    variant: Variant,
+    hash: u64,
+    mirror_hash: u64,
+    // Coordinates of the played moves in move order, the bottom cell for a pop.
+    moves: Vec<(usize, usize)>,
+    // Index in `moves` and color of every popped token, pops are only taken back in order.
+    pops: Vec<(usize, TokenColor)>,
+    // The first player to complete a line and the number of moves played at that point.
+    winner: Option<(TokenColor, usize)>,
+    // Cross-checks the incremental win detection with a full scan of the grid.
+    verify: bool,
 }
 
 impl Board {
     pub fn show(self: &Self) {
-        self.fields.iter().for_each(|row: &Vec<Field>| {
-            row.iter().for_each(|field: &Field| print!("{} ", field));
+        let cells: Vec<(usize, usize)> = self.winning_cells();
+
+        self.fields.iter().enumerate().for_each(|(i, row)| {
+            row.iter().enumerate().for_each(|(j, field)| {
+                if cells.contains(&(i, j)) {
+                    print!("{} ", field.highlighted());
+                } else {
+                    print!("{} ", field);
+                }
+            });
             println!()
         });
         println!()
     }
 
+    pub fn field(self: &Self, row: usize, column: usize) -> &Field {
+        return &self.fields[row][column];
+    }
+
     pub fn make_move(self: &mut Self, column: usize, color: TokenColor) -> Result<(), MoveError> {
-        if !self.is_move_legal(column) {
-            return Err(MoveError {
-                message: "illegal move".to_string(),
-            });
+        self.make_move_and_check_win(column, color)?;
+
+        return Ok(());
+    }
+
+    // Only the lines through the new token can have changed, so only those are checked.
+    pub fn make_move_and_check_win(
+        self: &mut Self,
+        column: usize,
+        color: TokenColor,
+    ) -> Result<bool, MoveError> {
+        if column >= self.columns {
+            return Err(MoveError::ColumnOutOfRange(column));
         }
 
-        for row in (0..self.rows).rev() {
-            match self.fields[row][column] {
-                Field::Empty => {
-                    self.fields[row][column] = Field::Token(color);
-                    if row == 0 {
-                        self.columns_full[column] = true;
-                    }
+        if self.columns_full[column] {
+            return Err(MoveError::IllegalMove(column));
+        }
 
-                    break;
-                }
+        let row: usize = (0..self.rows)
+            .rev()
+            .find(|&x| self.fields[x][column] == Field::Empty)
+            .unwrap();
 
-                Field::Token(_) => continue,
-            }
+        self.fields[row][column] = Field::Token(color);
+        self.hash ^= self.zobrist_key(row, column, color);
+        self.mirror_hash ^= self.zobrist_key(row, self.mirror(column), color);
+        if row == 0 {
+            self.columns_full[column] = true;
+        }
+
+        self.moves.push((row, column));
+
+        let won: bool = self.is_winning_token(row, column);
+
+        if won && self.winner.is_none() {
+            self.winner = Some((color, self.moves.len()));
+        }
+
+        return Ok(won);
+    }
+
+    // Takes the token of `color` from the bottom of the column. All tokens in the column move,
+    // so the whole board is scanned, and lines of both players can be completed at once.
+    pub fn pop_and_check_win(
+        self: &mut Self,
+        column: usize,
+        color: TokenColor,
+    ) -> Result<bool, MoveError> {
+        if column >= self.columns {
+            return Err(MoveError::ColumnOutOfRange(column));
+        }
+
+        if !self.is_pop_legal(column, color) {
+            return Err(MoveError::IllegalPop(column));
+        }
+
+        self.hash_column(column);
+        for row in (1..self.rows).rev() {
+            self.fields[row][column] = self.fields[row - 1][column].clone();
+        }
+        self.fields[0][column] = Field::Empty;
+        self.hash_column(column);
+
+        self.columns_full[column] = false;
+        self.moves.push((self.rows - 1, column));
+        self.pops.push((self.moves.len() - 1, color));
+
+        let winner: Option<TokenColor> = self.get_winner();
+
+        if self.winner.is_none() {
+            self.winner = winner.map(|x| (x, self.moves.len()));
+        }
+
+        return Ok(winner == Some(color));
+    }
+
+    pub fn play_move(self: &mut Self, mv: Move, color: TokenColor) -> Result<bool, MoveError> {
+        return match mv {
+            Move::Drop(column) => self.make_move_and_check_win(column, color),
+            Move::Pop(column) => self.pop_and_check_win(column, color),
+        };
+    }
+
+    pub fn play(self: &mut Self, moves: &[usize], first: TokenColor) -> Result<(), MoveError> {
+        let mut color: TokenColor = first;
+
+        for &column in moves {
+            self.make_move(column, color)?;
+            color = color.invert();
         }
 
         return Ok(());
@@ -84,21 +282,36 @@ impl Board {
 
     pub fn undo_move(self: &mut Self, column: usize) -> Result<(), MoveError> {
         if column >= self.columns {
-            return Err(MoveError {
-                message: "column index out of range".to_string(),
-            });
+            return Err(MoveError::ColumnOutOfRange(column));
         }
 
         if self.fields[self.rows - 1][column] == Field::Empty {
-            return Err(MoveError {
-                message: "column is empty".to_string(),
-            });
+            return Err(MoveError::EmptyColumn(column));
         }
 
-        for i in 0..self.rows {
-            if self.fields[i][column] != Field::Empty {
-                self.fields[i][column] = Field::Empty;
-                break;
+        let row: usize = (0..self.rows)
+            .find(|&x| self.fields[x][column] != Field::Empty)
+            .unwrap();
+
+        if let Field::Token(color) = self.fields[row][column] {
+            self.fields[row][column] = Field::Empty;
+            self.hash ^= self.zobrist_key(row, column, color);
+            self.mirror_hash ^= self.zobrist_key(row, self.mirror(column), color);
+        }
+
+        let index: usize = self
+            .moves
+            .iter()
+            .rposition(|&x| x == (row, column))
+            .unwrap();
+        self.moves.remove(index);
+
+        if let Some((_, count)) = self.winner {
+            if index + 1 == count && index == self.moves.len() {
+                self.winner = None;
+            } else if index < count {
+                // Taken back out of order, the remaining tokens are scanned again.
+                self.winner = self.get_winner().map(|x| (x, self.moves.len()));
             }
         }
 
@@ -109,11 +322,249 @@ impl Board {
         return Ok(());
     }
 
+    pub fn undo_pop(self: &mut Self, column: usize) -> Result<(), MoveError> {
+        if column >= self.columns {
+            return Err(MoveError::ColumnOutOfRange(column));
+        }
+
+        let Some(&(index, color)) = self.pops.last() else {
+            return Err(MoveError::NotPopped(column));
+        };
+
+        if index + 1 != self.moves.len() || self.moves[index] != (self.rows - 1, column) {
+            return Err(MoveError::NotPopped(column));
+        }
+
+        self.hash_column(column);
+        for row in 0..self.rows - 1 {
+            self.fields[row][column] = self.fields[row + 1][column].clone();
+        }
+        self.fields[self.rows - 1][column] = Field::Token(color);
+        self.hash_column(column);
+
+        self.columns_full[column] = self.fields[0][column] != Field::Empty;
+
+        if let Some((_, count)) = self.winner {
+            if count == self.moves.len() {
+                self.winner = None;
+            }
+        }
+
+        self.moves.pop();
+        self.pops.pop();
+
+        return Ok(());
+    }
+
+    pub fn undo_play(self: &mut Self, mv: Move) -> Result<(), MoveError> {
+        return match mv {
+            Move::Drop(column) => self.undo_move(column),
+            Move::Pop(column) => self.undo_pop(column),
+        };
+    }
+
     pub fn get_status(self: &Self) -> GameStatus {
-        return match self.get_winner() {
-            Some(color) => GameStatus::Finished(color),
+        let status: GameStatus = match self.winner {
+            Some((color, _)) => GameStatus::Finished(color),
             None => GameStatus::InProgress,
         };
+
+        if self.verify {
+            let scanned: GameStatus = match self.get_winner() {
+                Some(color) => GameStatus::Finished(color),
+                None => GameStatus::InProgress,
+            };
+
+            assert_eq!(
+                status, scanned,
+                "incremental win detection disagrees with the full scan"
+            );
+        }
+
+        return status;
+    }
+
+    // Every run of at least four tokens of the winner, as (row, column) coordinates from the top
+    // left. A single move can complete more than one line.
+    pub fn get_winning_lines(self: &Self) -> Option<(TokenColor, Vec<Vec<(usize, usize)>>)> {
+        let GameStatus::Finished(color) = self.get_status() else {
+            return None;
+        };
+
+        let token = |row: isize, column: isize| {
+            row >= 0
+                && column >= 0
+                && row < self.rows as isize
+                && column < self.columns as isize
+                && self.fields[row as usize][column as usize] == Field::Token(color)
+        };
+
+        let mut lines: Vec<Vec<(usize, usize)>> = vec![];
+
+        for row in 0..self.rows as isize {
+            for column in 0..self.columns as isize {
+                for (dr, dc) in DIRECTIONS {
+                    // Only start at the first token of a run
+                    if !token(row, column) || token(row - dr, column - dc) {
+                        continue;
+                    }
+
+                    let mut line: Vec<(usize, usize)> = vec![];
+                    let (mut r, mut c) = (row, column);
+
+                    while token(r, c) {
+                        line.push((r as usize, c as usize));
+                        r += dr;
+                        c += dc;
+                    }
+
+                    if line.len() >= 4 {
+                        lines.push(line);
+                    }
+                }
+            }
+        }
+
+        return Some((color, lines));
+    }
+
+    pub fn winning_cells(self: &Self) -> Vec<(usize, usize)> {
+        return match self.get_winning_lines() {
+            Some((_, lines)) => lines.concat(),
+            None => vec![],
+        };
+    }
+
+    // Number of moves played, the tokens on the board unless there were pops.
+    pub fn ply(self: &Self) -> usize {
+        return self.moves.len();
+    }
+
+    // Where the last token was dropped, None if the last move was a pop.
+    pub fn last_move(self: &Self) -> Option<(usize, usize)> {
+        if self.last_pop().is_some() {
+            return None;
+        }
+
+        return self.moves.last().copied();
+    }
+
+    fn last_pop(self: &Self) -> Option<TokenColor> {
+        let &(index, color) = self.pops.last()?;
+
+        return match index + 1 == self.moves.len() {
+            true => Some(color),
+            false => None,
+        };
+    }
+
+    // The player who made the last move.
+    fn last_color(self: &Self) -> Option<TokenColor> {
+        if let Some(color) = self.last_pop() {
+            return Some(color);
+        }
+
+        let &(row, column) = self.moves.last()?;
+
+        return match self.fields[row][column] {
+            Field::Token(color) => Some(color),
+            Field::Empty => None,
+        };
+    }
+
+    pub fn variant(self: &Self) -> Variant {
+        return self.variant;
+    }
+
+    pub fn set_variant(self: &mut Self, variant: Variant) {
+        self.variant = variant;
+    }
+
+    pub fn set_verify(self: &mut Self, verify: bool) {
+        self.verify = verify;
+    }
+
+    // Zobrist hash of the fields, kept up to date by make_move and undo_move.
+    pub fn hash(self: &Self) -> u64 {
+        return self.hash;
+    }
+
+    // Same for a position and its left-right mirror image.
+    pub fn canonical_hash(self: &Self) -> u64 {
+        return self.hash.min(self.mirror_hash);
+    }
+
+    pub fn mirror(self: &Self, column: usize) -> usize {
+        return self.columns - 1 - column;
+    }
+
+    pub fn is_symmetric(self: &Self) -> bool {
+        return self.hash == self.mirror_hash
+            && self
+                .fields
+                .iter()
+                .all(|row| row.iter().eq(row.iter().rev()));
+    }
+
+    // Toggles the keys of all tokens in the column, once before and once after they move.
+    fn hash_column(self: &mut Self, column: usize) {
+        for row in 0..self.rows {
+            if let Field::Token(color) = self.fields[row][column] {
+                self.hash ^= self.zobrist_key(row, column, color);
+                self.mirror_hash ^= self.zobrist_key(row, self.mirror(column), color);
+            }
+        }
+    }
+
+    fn zobrist_key(self: &Self, row: usize, column: usize, color: TokenColor) -> u64 {
+        let index: u64 = ((row * self.columns + column) * 2 + color as usize) as u64;
+
+        // splitmix64 finalizer, so keys are stable without storing a random table
+        let mut key: u64 = (index + 1).wrapping_mul(0x9E3779B97F4A7C15);
+        key = (key ^ (key >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        key = (key ^ (key >> 27)).wrapping_mul(0x94D049BB133111EB);
+
+        return key ^ (key >> 31);
+    }
+
+    pub fn is_full(self: &Self) -> bool {
+        return self.columns_full.iter().all(|x| *x);
+    }
+
+    // Whether a game that has not been won ends in a draw, with `history` the moves that led to
+    // the position. In PopOut that is also the third time the position comes up with the same
+    // side to move, or a game of POPOUT_MAX_PLIES moves.
+    pub fn is_draw(self: &Self, history: &[usize]) -> bool {
+        if self.is_full() {
+            return true;
+        }
+
+        if self.variant != Variant::PopOut {
+            return false;
+        }
+
+        if self.ply() >= POPOUT_MAX_PLIES {
+            return true;
+        }
+
+        let mut board: Board = self.clone();
+        let mut count: usize = 1;
+
+        for (ply, &index) in history.iter().enumerate().rev() {
+            if board
+                .undo_play(Move::from_index(index, self.columns))
+                .is_err()
+            {
+                break;
+            }
+
+            // Positions an even number of moves back have the same side to move
+            if (history.len() - ply) % 2 == 0 && board.hash() == self.hash() {
+                count += 1;
+            }
+        }
+
+        return count >= 3;
     }
 
     pub fn is_move_legal(self: &Self, column: usize) -> bool {
@@ -128,10 +579,61 @@ impl Board {
         return true;
     }
 
+    pub fn is_pop_legal(self: &Self, column: usize, color: TokenColor) -> bool {
+        return self.variant == Variant::PopOut
+            && column < self.columns
+            && self.fields[self.rows - 1][column] == Field::Token(color);
+    }
+
+    // Counts the tokens of the same color along the four lines through the given one.
+    fn is_winning_token(self: &Self, row: usize, column: usize) -> bool {
+        let Field::Token(color) = self.fields[row][column] else {
+            return false;
+        };
+
+        return self.completes_line(row, column, color);
+    }
+
+    // Whether a token of `color` on the cell would be part of four in a row, whatever the cell
+    // itself holds.
+    pub fn completes_line(self: &Self, row: usize, column: usize, color: TokenColor) -> bool {
+        for (dr, dc) in DIRECTIONS {
+            let mut count: usize = 1;
+
+            for sign in [1, -1] {
+                let (mut r, mut c) = (row as isize + sign * dr, column as isize + sign * dc);
+
+                while r >= 0
+                    && c >= 0
+                    && r < self.rows as isize
+                    && c < self.columns as isize
+                    && self.fields[r as usize][c as usize] == Field::Token(color)
+                {
+                    count += 1;
+                    r += sign * dr;
+                    c += sign * dc;
+                }
+            }
+
+            if count >= 4 {
+                return true;
+            }
+        }
+
+        return false;
+    }
+
+    // Only a pop can complete lines of both players at once, the player who popped wins then.
     fn get_winner(self: &Self) -> Option<TokenColor> {
+        let mut winners: Vec<TokenColor> = vec![];
+
         for row in 0..self.rows {
             for column in 0..self.columns {
-                if self.fields[row][column] == Field::Empty {
+                let Field::Token(color) = self.fields[row][column] else {
+                    continue;
+                };
+
+                if winners.contains(&color) {
                     continue;
                 }
 
@@ -141,10 +643,8 @@ impl Board {
                     && (self.fields[row][column + 1] == self.fields[row][column + 2])
                     && (self.fields[row][column + 2] == self.fields[row][column + 3])
                 {
-                    return match self.fields[row][column] {
-                        Field::Token(color) => Some(color),
-                        Field::Empty => None,
-                    };
+                    winners.push(color);
+                    continue;
                 }
 
                 // Columns
@@ -153,10 +653,8 @@ impl Board {
                     && (self.fields[row + 1][column] == self.fields[row + 2][column])
                     && (self.fields[row + 2][column] == self.fields[row + 3][column])
                 {
-                    return match self.fields[row][column] {
-                        Field::Token(color) => Some(color),
-                        Field::Empty => None,
-                    };
+                    winners.push(color);
+                    continue;
                 }
 
                 // Left diagonal
@@ -165,10 +663,8 @@ impl Board {
                     && (self.fields[row - 1][column - 1] == self.fields[row - 2][column - 2])
                     && (self.fields[row - 2][column - 2] == self.fields[row - 3][column - 3])
                 {
-                    return match self.fields[row][column] {
-                        Field::Token(color) => Some(color),
-                        Field::Empty => None,
-                    };
+                    winners.push(color);
+                    continue;
                 }
 
                 // Right diagonal
@@ -177,15 +673,16 @@ impl Board {
                     && (self.fields[row - 1][column + 1] == self.fields[row - 2][column + 2])
                     && (self.fields[row - 2][column + 2] == self.fields[row - 3][column + 3])
                 {
-                    return match self.fields[row][column] {
-                        Field::Token(color) => Some(color),
-                        Field::Empty => None,
-                    };
+                    winners.push(color);
+                    continue;
                 }
             }
         }
 
-        return None;
+        return match winners.len() {
+            0 | 1 => winners.first().copied(),
+            _ => self.last_color().or(winners.first().copied()),
+        };
     }
 }
 
@@ -196,6 +693,799 @@ impl Default for Board {
             columns: DEFAULT_COLUMNS,
             fields: vec![vec![Field::Empty; DEFAULT_COLUMNS]; DEFAULT_ROWS],
             columns_full: vec![false; DEFAULT_COLUMNS],
+            hash: 0,
+            mirror_hash: 0,
+            variant: Variant::Standard,
+            moves: vec![],
+            pops: vec![],
+            winner: None,
+            verify: false,
         };
     }
 }
+
+// Moves are numbered as in Move::index.
+impl Game for Board {
+    type Move = Move;
+
+    fn decode(self: &Self, index: usize) -> Move {
+        return Move::from_index(index, self.columns);
+    }
+
+    fn encode(self: &Self, mv: Move) -> usize {
+        return mv.index(self.columns);
+    }
+
+    fn legal_moves(self: &Self, color: TokenColor) -> Vec<usize> {
+        if self.get_status() != GameStatus::InProgress || self.is_full() {
+            return vec![];
+        }
+
+        // Repetitions need the history, the search only stops at the move limit
+        if self.variant == Variant::PopOut && self.ply() >= POPOUT_MAX_PLIES {
+            return vec![];
+        }
+
+        let drops = (0..self.columns).filter(|&x| self.is_move_legal(x));
+        let pops = (0..self.columns)
+            .filter(|&x| self.is_pop_legal(x, color))
+            .map(|x| self.columns + x);
+
+        return drops.chain(pops).collect();
+    }
+
+    fn apply(self: &mut Self, index: usize, color: TokenColor) -> Result<GameStatus, MoveError> {
+        self.play_move(self.decode(index), color)?;
+
+        return Ok(self.get_status());
+    }
+
+    fn undo(self: &mut Self, index: usize) -> Result<(), MoveError> {
+        return self.undo_play(self.decode(index));
+    }
+
+    fn status(self: &Self) -> GameStatus {
+        return self.get_status();
+    }
+
+    fn ply(self: &Self) -> usize {
+        return Board::ply(self);
+    }
+
+    fn hash(self: &Self) -> u64 {
+        return Board::hash(self);
+    }
+
+    fn canonical_hash(self: &Self) -> u64 {
+        return Board::canonical_hash(self);
+    }
+
+    fn mirror(self: &Self, index: usize) -> usize {
+        let mv: Move = match self.decode(index) {
+            Move::Drop(column) => Move::Drop(Board::mirror(self, column)),
+            Move::Pop(column) => Move::Pop(Board::mirror(self, column)),
+        };
+
+        return self.encode(mv);
+    }
+
+    fn is_symmetric(self: &Self) -> bool {
+        return Board::is_symmetric(self);
+    }
+
+    fn tactical_moves(
+        self: &Self,
+        color: TokenColor,
+        columns: Vec<usize>,
+        prune: bool,
+    ) -> Vec<usize> {
+        return Board::tactical_moves(self, color, columns, prune);
+    }
+
+    fn forced_result(self: &Self, to_move: TokenColor) -> Option<(TokenColor, usize)> {
+        return Board::forced_result(self, to_move);
+    }
+
+    fn evaluate(self: &Self, color: TokenColor, to_move: TokenColor) -> f64 {
+        return Board::evaluate(self, color, to_move);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::notation::parse_moves;
+    use crate::node::{
+        backup::Backup,
+        node::Node,
+        ordering::{MoveOrdering, OrderingOptions},
+        score::win_value,
+        stats::SearchStats,
+    };
+    use proptest::prelude::*;
+
+    fn with_tokens(tokens: &[(usize, usize, TokenColor)]) -> Board {
+        let mut board: Board = Board::default();
+
+        for &(row, column, color) in tokens {
+            board.fields[row][column] = Field::Token(color);
+        }
+
+        return board;
+    }
+
+    // Result of the full scan, checked against the lines through each of the tokens.
+    fn status_of(tokens: &[(usize, usize, TokenColor)]) -> GameStatus {
+        let board: Board = with_tokens(tokens);
+        let scanned: GameStatus = match board.get_winner() {
+            Some(color) => GameStatus::Finished(color),
+            None => GameStatus::InProgress,
+        };
+
+        let incremental: bool = tokens
+            .iter()
+            .any(|&(row, column, _)| board.is_winning_token(row, column));
+
+        assert_eq!(incremental, scanned != GameStatus::InProgress);
+
+        return scanned;
+    }
+
+    // Every run of four cells that fits on the board, as (row, column, row step, column step).
+    fn lines(board: &Board) -> Vec<(usize, usize, isize, isize)> {
+        let mut lines: Vec<(usize, usize, isize, isize)> = vec![];
+
+        for row in 0..board.rows {
+            for column in 0..board.columns {
+                for (dr, dc) in DIRECTIONS {
+                    let end_row: isize = row as isize + 3 * dr;
+                    let end_column: isize = column as isize + 3 * dc;
+
+                    if (0..board.rows as isize).contains(&end_row)
+                        && (0..board.columns as isize).contains(&end_column)
+                    {
+                        lines.push((row, column, dr, dc));
+                    }
+                }
+            }
+        }
+
+        return lines;
+    }
+
+    fn cells(line: (usize, usize, isize, isize), length: usize) -> Vec<(usize, usize)> {
+        let (row, column, dr, dc) = line;
+
+        return (0..length as isize)
+            .map(|i| {
+                (
+                    (row as isize + i * dr) as usize,
+                    (column as isize + i * dc) as usize,
+                )
+            })
+            .collect();
+    }
+
+    // Straightforward check of every cell in every direction, without shared bounds arithmetic.
+    fn naive_winner(board: &Board) -> Option<TokenColor> {
+        for row in 0..board.rows as isize {
+            for column in 0..board.columns as isize {
+                let Field::Token(color) = board.fields[row as usize][column as usize] else {
+                    continue;
+                };
+
+                for (dr, dc) in DIRECTIONS {
+                    let won: bool = (1..4).all(|i| {
+                        let (r, c) = (row + i * dr, column + i * dc);
+
+                        r >= 0
+                            && c >= 0
+                            && r < board.rows as isize
+                            && c < board.columns as isize
+                            && board.fields[r as usize][c as usize] == Field::Token(color)
+                    });
+
+                    if won {
+                        return Some(color);
+                    }
+                }
+            }
+        }
+
+        return None;
+    }
+
+    fn assert_consistent(board: &Board) {
+        let mut hash: u64 = 0;
+
+        for row in 0..board.rows {
+            for column in 0..board.columns {
+                if let Field::Token(color) = board.fields[row][column] {
+                    hash ^= board.zobrist_key(row, column, color);
+                }
+            }
+        }
+
+        assert_eq!(board.hash(), hash);
+
+        for column in 0..board.columns {
+            assert_eq!(
+                board.columns_full[column],
+                board.fields[0][column] != Field::Empty
+            );
+        }
+    }
+
+    // Plays the columns in order, skipping illegal ones, until the game is decided or the board
+    // is full, and returns the columns that were played.
+    fn play_game(board: &mut Board, columns: &[usize]) -> Vec<usize> {
+        let mut played: Vec<usize> = vec![];
+        let mut color: TokenColor = TokenColor::Yellow;
+
+        for &column in columns {
+            if board.get_status() != GameStatus::InProgress || board.is_full() {
+                break;
+            }
+
+            if board.make_move(column, color).is_err() {
+                continue;
+            }
+
+            played.push(column);
+            color = color.invert();
+        }
+
+        return played;
+    }
+
+    #[test]
+    fn empty_board_is_in_progress() {
+        let board: Board = Board::default();
+
+        assert_eq!(board.get_status(), GameStatus::InProgress);
+        assert!(!board.is_full());
+        assert_eq!(board.hash(), 0);
+    }
+
+    #[test]
+    fn detects_four_in_every_direction_at_every_offset() {
+        let board: Board = Board::default();
+        let lines: Vec<(usize, usize, isize, isize)> = lines(&board);
+
+        // 24 horizontal, 21 vertical and 12 in each diagonal direction on a 6x7 board
+        assert_eq!(lines.len(), 69);
+
+        for line in lines {
+            for color in [TokenColor::Red, TokenColor::Yellow] {
+                let tokens: Vec<(usize, usize, TokenColor)> = cells(line, 4)
+                    .into_iter()
+                    .map(|(row, column)| (row, column, color))
+                    .collect();
+
+                assert_eq!(
+                    status_of(&tokens),
+                    GameStatus::Finished(color),
+                    "line {:?}",
+                    line
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn three_in_a_line_or_a_broken_line_is_not_a_win() {
+        let board: Board = Board::default();
+
+        for line in lines(&board) {
+            let run: Vec<(usize, usize)> = cells(line, 4);
+
+            for gap in 0..4 {
+                let mut tokens: Vec<(usize, usize, TokenColor)> = run
+                    .iter()
+                    .map(|&(row, column)| (row, column, TokenColor::Red))
+                    .collect();
+                tokens[gap].2 = TokenColor::Yellow;
+
+                assert_eq!(
+                    status_of(&tokens),
+                    GameStatus::InProgress,
+                    "line {:?}, gap {}",
+                    line,
+                    gap
+                );
+
+                tokens.remove(gap);
+
+                assert_eq!(
+                    status_of(&tokens),
+                    GameStatus::InProgress,
+                    "line {:?}, missing {}",
+                    line,
+                    gap
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn lines_do_not_wrap_around_edges() {
+        // Three at the end of the bottom row and one at the start of the row above
+        let tokens: Vec<(usize, usize, TokenColor)> = vec![
+            (5, 4, TokenColor::Red),
+            (5, 5, TokenColor::Red),
+            (5, 6, TokenColor::Red),
+            (4, 0, TokenColor::Red),
+        ];
+
+        assert_eq!(status_of(&tokens), GameStatus::InProgress);
+
+        // Diagonals touching the right and left edges
+        let tokens: Vec<(usize, usize, TokenColor)> = vec![
+            (5, 5, TokenColor::Yellow),
+            (4, 6, TokenColor::Yellow),
+            (3, 0, TokenColor::Yellow),
+            (2, 1, TokenColor::Yellow),
+        ];
+
+        assert_eq!(status_of(&tokens), GameStatus::InProgress);
+    }
+
+    #[test]
+    fn tokens_stack_from_the_bottom() {
+        let mut board: Board = Board::default();
+
+        board.make_move(2, TokenColor::Yellow).unwrap();
+        board.make_move(2, TokenColor::Red).unwrap();
+
+        assert_eq!(*board.field(5, 2), Field::Token(TokenColor::Yellow));
+        assert_eq!(*board.field(4, 2), Field::Token(TokenColor::Red));
+        assert_eq!(*board.field(3, 2), Field::Empty);
+    }
+
+    #[test]
+    fn column_becomes_full_after_six_tokens() {
+        let mut board: Board = Board::default();
+        let mut color: TokenColor = TokenColor::Yellow;
+
+        for i in 0..board.rows {
+            assert!(!board.columns_full[3], "full after {} tokens", i);
+            assert!(board.is_move_legal(3));
+
+            board.make_move(3, color).unwrap();
+            color = color.invert();
+        }
+
+        assert!(board.columns_full[3]);
+        assert!(!board.is_move_legal(3));
+        assert_eq!(board.make_move(3, color), Err(MoveError::IllegalMove(3)));
+
+        board.undo_move(3).unwrap();
+
+        assert!(!board.columns_full[3]);
+        assert!(board.is_move_legal(3));
+    }
+
+    #[test]
+    fn drawn_game_fills_the_board() {
+        let mut board: Board = Board::default();
+        let moves: Vec<usize> = parse_moves("366643626164415421524324025000001335352511").unwrap();
+
+        for (i, &column) in moves.iter().enumerate() {
+            assert!(!board.is_full());
+            assert_eq!(board.get_status(), GameStatus::InProgress);
+
+            let color: TokenColor = match i % 2 {
+                0 => TokenColor::Yellow,
+                _ => TokenColor::Red,
+            };
+
+            board.make_move(column, color).unwrap();
+        }
+
+        assert!(board.is_full());
+        assert_eq!(board.get_status(), GameStatus::InProgress);
+        assert!((0..board.columns).all(|x| !board.is_move_legal(x)));
+    }
+
+    #[test]
+    fn illegal_moves_are_rejected_without_changes() {
+        let mut board: Board = Board::default();
+        board.make_move(0, TokenColor::Yellow).unwrap();
+
+        let before: Board = board.clone();
+
+        assert_eq!(
+            board.make_move(board.columns, TokenColor::Red),
+            Err(MoveError::ColumnOutOfRange(board.columns))
+        );
+        assert_eq!(
+            board.make_move(usize::MAX, TokenColor::Red),
+            Err(MoveError::ColumnOutOfRange(usize::MAX))
+        );
+        assert!(!board.is_move_legal(board.columns));
+        assert_eq!(
+            board.undo_move(board.columns),
+            Err(MoveError::ColumnOutOfRange(board.columns))
+        );
+        assert_eq!(board.undo_move(1), Err(MoveError::EmptyColumn(1)));
+
+        assert_eq!(board.fields, before.fields);
+        assert_eq!(board.columns_full, before.columns_full);
+        assert_eq!(board.hash(), before.hash());
+    }
+
+    #[test]
+    fn play_stops_at_the_first_illegal_move() {
+        let mut board: Board = Board::default();
+
+        assert!(board.play(&[3, 3, 3, 3, 3, 3], TokenColor::Yellow).is_ok());
+        assert!(board.play(&[2, 3], TokenColor::Yellow).is_err());
+
+        assert_eq!(*board.field(5, 2), Field::Token(TokenColor::Yellow));
+        assert_eq!(*board.field(0, 3), Field::Token(TokenColor::Red));
+    }
+
+    #[test]
+    fn winning_move_is_reported_and_undone() {
+        let mut board: Board = Board::default();
+        board.set_verify(true);
+
+        board.play(&[0, 0, 1, 1, 2], TokenColor::Yellow).unwrap();
+
+        assert!(!board.make_move_and_check_win(2, TokenColor::Red).unwrap());
+        assert_eq!(board.last_move(), Some((4, 2)));
+        assert!(board
+            .make_move_and_check_win(3, TokenColor::Yellow)
+            .unwrap());
+        assert_eq!(board.last_move(), Some((5, 3)));
+        assert_eq!(board.get_status(), GameStatus::Finished(TokenColor::Yellow));
+
+        board.undo_move(3).unwrap();
+
+        assert_eq!(board.get_status(), GameStatus::InProgress);
+        assert_eq!(board.last_move(), Some((4, 2)));
+    }
+
+    #[test]
+    fn win_stays_after_later_moves_are_undone() {
+        let mut board: Board = Board::default();
+        board.set_verify(true);
+        board
+            .play(&[0, 6, 1, 6, 2, 6, 3], TokenColor::Yellow)
+            .unwrap();
+
+        board.make_move(5, TokenColor::Red).unwrap();
+        board.undo_move(5).unwrap();
+
+        assert_eq!(board.get_status(), GameStatus::Finished(TokenColor::Yellow));
+    }
+
+    #[test]
+    fn out_of_order_undo_rescans() {
+        let mut board: Board = Board::default();
+        board.set_verify(true);
+        board
+            .play(&[0, 6, 1, 6, 2, 5, 3, 4], TokenColor::Yellow)
+            .unwrap();
+
+        assert_eq!(board.get_status(), GameStatus::Finished(TokenColor::Yellow));
+
+        board.undo_move(3).unwrap();
+
+        assert_eq!(board.get_status(), GameStatus::InProgress);
+        assert_eq!(board.last_move(), Some((5, 4)));
+    }
+
+    #[test]
+    fn winning_lines_are_reported_with_the_winner() {
+        let mut board: Board = Board::default();
+
+        assert_eq!(board.get_winning_lines(), None);
+
+        board
+            .play(&[0, 0, 1, 1, 2, 2, 3], TokenColor::Yellow)
+            .unwrap();
+
+        assert_eq!(
+            board.get_winning_lines(),
+            Some((
+                TokenColor::Yellow,
+                vec![vec![(5, 0), (5, 1), (5, 2), (5, 3)]]
+            ))
+        );
+    }
+
+    #[test]
+    fn one_move_can_complete_several_lines() {
+        // The token at (5, 2) completes a row of five and a column of four
+        let tokens: Vec<(usize, usize, TokenColor)> = [
+            (5, 0),
+            (5, 1),
+            (5, 2),
+            (5, 3),
+            (5, 4),
+            (4, 2),
+            (3, 2),
+            (2, 2),
+        ]
+        .iter()
+        .map(|&(row, column)| (row, column, TokenColor::Red))
+        .collect();
+
+        let mut board: Board = with_tokens(&tokens);
+        board.winner = Some((TokenColor::Red, tokens.len()));
+
+        let (color, lines) = board.get_winning_lines().unwrap();
+
+        assert_eq!(color, TokenColor::Red);
+        assert_eq!(
+            lines,
+            vec![
+                vec![(2, 2), (3, 2), (4, 2), (5, 2)],
+                vec![(5, 0), (5, 1), (5, 2), (5, 3), (5, 4)],
+            ]
+        );
+        assert!(board.is_winning_token(5, 2));
+    }
+
+    fn popout(tokens: &[(usize, usize, TokenColor)]) -> Board {
+        let mut board: Board = with_tokens(tokens);
+        board.set_variant(Variant::PopOut);
+
+        return board;
+    }
+
+    #[test]
+    fn pop_moves_the_column_down_and_back() {
+        let mut board: Board = Board::default();
+        board.set_variant(Variant::PopOut);
+        board.play(&[3, 3, 3, 4], TokenColor::Yellow).unwrap();
+
+        let before: Board = board.clone();
+
+        assert_eq!(
+            board.pop_and_check_win(3, TokenColor::Red),
+            Err(MoveError::IllegalPop(3))
+        );
+        assert!(!board.pop_and_check_win(3, TokenColor::Yellow).unwrap());
+
+        assert_eq!(*board.field(5, 3), Field::Token(TokenColor::Red));
+        assert_eq!(*board.field(4, 3), Field::Token(TokenColor::Yellow));
+        assert_eq!(*board.field(3, 3), Field::Empty);
+        assert_eq!(board.ply(), 5);
+        assert_eq!(board.last_move(), None);
+        assert_consistent(&board);
+
+        board.make_move(4, TokenColor::Red).unwrap();
+        assert_eq!(board.undo_pop(3), Err(MoveError::NotPopped(3)));
+
+        board.undo_play(Move::Drop(4)).unwrap();
+        board.undo_play(Move::Pop(3)).unwrap();
+
+        assert_eq!(board.fields, before.fields);
+        assert_eq!(board.hash(), before.hash());
+        assert_eq!(board.ply(), before.ply());
+        assert_consistent(&board);
+    }
+
+    #[test]
+    fn popout_draws_by_repetition() {
+        let mut board: Board = Board::default();
+        board.set_variant(Variant::PopOut);
+
+        // Both players drop a token and pop it again, which leaves the empty board
+        let cycle: [Move; 4] = [Move::Drop(0), Move::Drop(1), Move::Pop(0), Move::Pop(1)];
+        let mut history: Vec<usize> = vec![];
+
+        for round in 0..2 {
+            for (i, &mv) in cycle.iter().enumerate() {
+                assert!(!board.is_draw(&history));
+
+                board
+                    .play_move(mv, [TokenColor::Yellow, TokenColor::Red][i % 2])
+                    .unwrap();
+                history.push(board.encode(mv));
+            }
+
+            assert_eq!(board.is_draw(&history), round == 1);
+        }
+
+        assert_eq!(board.ply(), 8);
+        assert!(!board.is_full());
+
+        board.set_variant(Variant::Standard);
+        assert!(!board.is_draw(&history));
+    }
+
+    #[test]
+    fn popout_draws_at_the_move_limit() {
+        let mut board: Board = Board::default();
+        board.set_variant(Variant::PopOut);
+
+        // Without a history only the length of the game counts
+        while board.ply() < POPOUT_MAX_PLIES {
+            assert!(!board.is_draw(&[]));
+
+            let color: TokenColor = [TokenColor::Yellow, TokenColor::Red][board.ply() % 2];
+            let column: usize = [0, 6][board.ply() % 2];
+
+            match board.ply() % 4 < 2 {
+                true => board.play_move(Move::Drop(column), color).unwrap(),
+                false => board.play_move(Move::Pop(column), color).unwrap(),
+            };
+        }
+
+        assert!(board.is_draw(&[]));
+        assert!(board.legal_moves(TokenColor::Yellow).is_empty());
+    }
+
+    #[test]
+    fn pops_are_only_legal_in_popout() {
+        let mut board: Board = Board::default();
+        board.play(&[0, 1], TokenColor::Yellow).unwrap();
+
+        assert_eq!(
+            board.legal_moves(TokenColor::Yellow),
+            (0..7).collect::<Vec<usize>>()
+        );
+        assert_eq!(
+            board.play_move(Move::Pop(0), TokenColor::Yellow),
+            Err(MoveError::IllegalPop(0))
+        );
+
+        board.set_variant(Variant::PopOut);
+
+        assert_eq!(
+            board.legal_moves(TokenColor::Yellow),
+            vec![0, 1, 2, 3, 4, 5, 6, 7]
+        );
+        assert_eq!(
+            board.legal_moves(TokenColor::Red),
+            vec![0, 1, 2, 3, 4, 5, 6, 8]
+        );
+        assert_eq!(board.decode(8), Move::Pop(1));
+        assert_eq!(Game::mirror(&board, 8), board.encode(Move::Pop(5)));
+    }
+
+    #[test]
+    fn pop_can_win_for_the_opponent() {
+        // Red's token above the popped one completes the bottom row
+        let mut board: Board = popout(&[
+            (5, 0, TokenColor::Red),
+            (5, 1, TokenColor::Red),
+            (5, 2, TokenColor::Red),
+            (5, 3, TokenColor::Yellow),
+            (4, 3, TokenColor::Red),
+        ]);
+
+        assert!(!board.pop_and_check_win(3, TokenColor::Yellow).unwrap());
+        assert_eq!(board.get_status(), GameStatus::Finished(TokenColor::Red));
+
+        board.undo_pop(3).unwrap();
+        assert_eq!(board.get_status(), GameStatus::InProgress);
+    }
+
+    #[test]
+    fn simultaneous_lines_go_to_the_player_who_popped() {
+        // After the pop red completes the bottom row and yellow the one above it
+        let tokens: Vec<(usize, usize, TokenColor)> = vec![
+            (5, 0, TokenColor::Red),
+            (5, 1, TokenColor::Red),
+            (5, 2, TokenColor::Red),
+            (4, 0, TokenColor::Yellow),
+            (4, 1, TokenColor::Yellow),
+            (4, 2, TokenColor::Yellow),
+            (5, 3, TokenColor::Yellow),
+            (4, 3, TokenColor::Red),
+            (3, 3, TokenColor::Yellow),
+        ];
+
+        let mut board: Board = popout(&tokens);
+        board.set_verify(true);
+
+        assert_eq!(
+            board.apply(Move::Pop(3).index(7), TokenColor::Yellow),
+            Ok(GameStatus::Finished(TokenColor::Yellow))
+        );
+        assert_eq!(board.get_winning_lines().unwrap().1.len(), 1);
+        assert!(board.legal_moves(TokenColor::Red).is_empty());
+
+        // The search finds the pop
+        let mut board: Board = popout(&tokens);
+        let mut root: Node = Node::new(TokenColor::Red, 0, GameStatus::InProgress);
+        root.search(
+            &mut board,
+            0,
+            0,
+            TokenColor::Yellow,
+            Backup::Minimax,
+            &mut MoveOrdering::new(OrderingOptions::all()),
+            &mut SearchStats::default(),
+        );
+
+        assert_eq!(root.best_child(TokenColor::Yellow).unwrap().column, 10);
+        assert_eq!(root.value, Some(win_value(1)));
+    }
+
+    proptest! {
+        #[test]
+        fn undo_restores_every_position(columns in prop::collection::vec(0..7usize, 0..60)) {
+            let mut board: Board = Board::default();
+            let mut positions: Vec<Board> = vec![board.clone()];
+            let mut played: Vec<usize> = vec![];
+            let mut color: TokenColor = TokenColor::Yellow;
+
+            // Keeps playing after a win, undo does not depend on the game being in progress
+            for column in columns {
+                if board.make_move(column, color).is_ok() {
+                    played.push(column);
+                    positions.push(board.clone());
+                    color = color.invert();
+                }
+
+                assert_consistent(&board);
+            }
+
+            while let Some(column) = played.pop() {
+                positions.pop();
+                board.undo_move(column).unwrap();
+
+                let expected: &Board = positions.last().unwrap();
+
+                prop_assert_eq!(&board.fields, &expected.fields);
+                prop_assert_eq!(&board.columns_full, &expected.columns_full);
+                prop_assert_eq!(board.hash(), expected.hash());
+                prop_assert_eq!(board.canonical_hash(), expected.canonical_hash());
+            }
+
+            prop_assert_eq!(board.hash(), 0);
+            prop_assert!(board.fields.iter().flatten().all(|x| *x == Field::Empty));
+        }
+
+        #[test]
+        fn matches_naive_reference_during_games(columns in prop::collection::vec(0..7usize, 0..80)) {
+            let mut board: Board = Board::default();
+            board.set_verify(true);
+            let mut color: TokenColor = TokenColor::Yellow;
+
+            for column in columns {
+                if board.get_status() != GameStatus::InProgress {
+                    break;
+                }
+
+                let Ok(won) = board.make_move_and_check_win(column, color) else {
+                    prop_assert!(board.columns_full[column]);
+                    continue;
+                };
+
+                let expected: GameStatus = match naive_winner(&board) {
+                    Some(winner) => GameStatus::Finished(winner),
+                    None => GameStatus::InProgress,
+                };
+
+                prop_assert_eq!(board.get_status(), expected);
+                prop_assert_eq!(won, expected != GameStatus::InProgress);
+
+                // Only the player who just moved can have completed a line
+                if let GameStatus::Finished(winner) = expected {
+                    prop_assert_eq!(winner, color);
+                }
+
+                color = color.invert();
+            }
+        }
+
+        #[test]
+        fn mirrored_games_have_the_same_canonical_hash(columns in prop::collection::vec(0..7usize, 0..42)) {
+            let mut board: Board = Board::default();
+            let played: Vec<usize> = play_game(&mut board, &columns);
+
+            let mirrored_moves: Vec<usize> = played.iter().map(|&x| board.mirror(x)).collect();
+            let mut mirrored: Board = Board::default();
+            mirrored.play(&mirrored_moves, TokenColor::Yellow).unwrap();
+
+            prop_assert_eq!(board.canonical_hash(), mirrored.canonical_hash());
+            prop_assert_eq!(board.get_status(), mirrored.get_status());
+        }
+    }
+}
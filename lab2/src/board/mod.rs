@@ -1,2 +1,4 @@
 pub mod board;
+pub mod notation;
+pub mod tactics;
 pub mod token;
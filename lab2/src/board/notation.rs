@@ -0,0 +1,30 @@
+// Move sequences are written as one digit per move, the 0-based column index, e.g. "3342".
+// The empty sequence is written as "-".
+
+pub fn format_moves(moves: &[usize]) -> String {
+    if moves.is_empty() {
+        return "-".to_string();
+    }
+
+    return moves.iter().map(|x| x.to_string()).collect();
+}
+
+pub fn parse_moves(text: &str) -> Option<Vec<usize>> {
+    if text == "-" {
+        return Some(vec![]);
+    }
+
+    return text
+        .chars()
+        .map(|x| x.to_digit(10).map(|x| x as usize))
+        .collect();
+}
+
+// Cells of a line as (row, column) pairs, e.g. "(5,0) (5,1) (5,2) (5,3)".
+pub fn format_line(line: &[(usize, usize)]) -> String {
+    return line
+        .iter()
+        .map(|(row, column)| format!("({},{})", row, column))
+        .collect::<Vec<String>>()
+        .join(" ");
+}
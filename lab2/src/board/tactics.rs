@@ -0,0 +1,308 @@
+use super::board::{Board, Field, Variant};
+use super::token::TokenColor;
+
+// Weight of a threat in the evaluation, and of one on a row that favours its owner in the
+// zugzwang at the end of the game.
+const THREAT_WEIGHT: f64 = 0.05;
+const ZUGZWANG_WEIGHT: f64 = 0.15;
+
+// Immediate tactics around threats, empty cells that would complete a line of four for one of
+// the players. They only look at the position, nothing is played on the board.
+impl Board {
+    // The row a token dropped into the column lands on.
+    pub fn drop_row(self: &Self, column: usize) -> Option<usize> {
+        if !self.is_move_legal(column) {
+            return None;
+        }
+
+        return (0..self.rows)
+            .rev()
+            .find(|&x| *self.field(x, column) == Field::Empty);
+    }
+
+    pub fn is_threat(self: &Self, row: usize, column: usize, color: TokenColor) -> bool {
+        if *self.field(row, column) != Field::Empty {
+            return false;
+        }
+
+        return self.completes_line(row, column, color);
+    }
+
+    // Columns in which `color` completes a line right away.
+    pub fn winning_moves(self: &Self, color: TokenColor) -> Vec<usize> {
+        return (0..self.columns)
+            .filter(|&x| match self.drop_row(x) {
+                Some(row) => self.is_threat(row, x, color),
+                None => false,
+            })
+            .collect();
+    }
+
+    // Columns `color` has to play so the opponent does not win with its next move.
+    pub fn forced_blocks(self: &Self, color: TokenColor) -> Vec<usize> {
+        return self.winning_moves(color.invert());
+    }
+
+    // Playing the column puts the token right below a threat of the opponent, which can then
+    // complete its line on top of it.
+    pub fn is_move_unsafe(self: &Self, column: usize, color: TokenColor) -> bool {
+        return match self.drop_row(column) {
+            Some(row) if row > 0 => self.is_threat(row - 1, column, color.invert()),
+            _ => false,
+        };
+    }
+
+    // `color` wins with its next move whatever the opponent does in between, either with two
+    // winning columns or with two threats on top of each other, where blocking the lower one
+    // lets it play the upper one.
+    pub fn has_double_threat(self: &Self, color: TokenColor) -> bool {
+        let wins: Vec<usize> = self.winning_moves(color);
+
+        if wins.len() >= 2 {
+            return true;
+        }
+
+        return wins.iter().any(|&x| self.is_move_unsafe(x, color.invert()));
+    }
+
+    // A win on the next move for the side to move, or otherwise a double threat of the
+    // opponent it cannot stop, with the winner and the ply of the winning move.
+    pub fn forced_result(self: &Self, to_move: TokenColor) -> Option<(TokenColor, usize)> {
+        if !self.winning_moves(to_move).is_empty() {
+            return Some((to_move, self.ply() + 1));
+        }
+
+        // A pop can break up any threat
+        if self.variant() == Variant::PopOut {
+            return None;
+        }
+
+        if self.has_double_threat(to_move.invert()) {
+            return Some((to_move.invert(), self.ply() + 2));
+        }
+
+        return None;
+    }
+
+    // Narrows the columns, in the given order, down to what tactics leave for `color` to play:
+    // a winning move if there is one. With `prune` also the blocks of the opponent's winning
+    // moves, otherwise the moves that do not hand the opponent a win. Those dropped moves lose
+    // at once, so this keeps the minimax value, but changes a mean over the children, see
+    // Backup::prunes_tactics. In PopOut the moves include pops, by their index, and a pop can
+    // block as well, so only a winning move is singled out.
+    pub fn tactical_moves(
+        self: &Self,
+        color: TokenColor,
+        columns: Vec<usize>,
+        prune: bool,
+    ) -> Vec<usize> {
+        let wins: Vec<usize> = self.winning_moves(color);
+
+        if let Some(&column) = columns.iter().find(|x| wins.contains(x)) {
+            return vec![column];
+        }
+
+        if !prune || self.variant() == Variant::PopOut {
+            return columns;
+        }
+
+        let blocks: Vec<usize> = self.forced_blocks(color);
+
+        if !blocks.is_empty() {
+            return columns.into_iter().filter(|x| blocks.contains(x)).collect();
+        }
+
+        let safe: Vec<usize> = columns
+            .iter()
+            .copied()
+            .filter(|&x| !self.is_move_unsafe(x, color))
+            .collect();
+
+        if safe.is_empty() {
+            return columns;
+        }
+
+        return safe;
+    }
+
+    // Threats of `color` not yet playable, by the parity of their row counted from 1 at the
+    // bottom: (odd, even).
+    pub fn threats(self: &Self, color: TokenColor) -> (usize, usize) {
+        let mut counts: (usize, usize) = (0, 0);
+
+        for column in 0..self.columns {
+            let Some(lowest) = self.drop_row(column) else {
+                continue;
+            };
+
+            for row in 0..lowest {
+                if !self.is_threat(row, column, color) {
+                    continue;
+                }
+
+                match (self.rows - row) % 2 {
+                    1 => counts.0 += 1,
+                    _ => counts.1 += 1,
+                }
+            }
+        }
+
+        return counts;
+    }
+
+    // Heuristic value of a quiet position for `color`, between -1 and 1, with `to_move` to
+    // play next. When the board fills up the first player ends up getting the odd rows and
+    // the second the even ones, so threats on those rows are worth more to their owners.
+    pub fn evaluate(self: &Self, color: TokenColor, to_move: TokenColor) -> f64 {
+        let first: TokenColor = match self.ply() % 2 {
+            0 => to_move,
+            _ => to_move.invert(),
+        };
+
+        let score = |side: TokenColor| -> f64 {
+            let (odd, even) = self.threats(side);
+            let (good, other) = match side == first {
+                true => (odd, even),
+                false => (even, odd),
+            };
+
+            return good as f64 * ZUGZWANG_WEIGHT + other as f64 * THREAT_WEIGHT;
+        };
+
+        return (score(color) - score(color.invert())).clamp(-1.0, 1.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::board::GameStatus;
+    use crate::board::notation::parse_moves;
+    use proptest::prelude::*;
+
+    fn position(moves: &str) -> Board {
+        let mut board: Board = Board::default();
+        board
+            .play(&parse_moves(moves).unwrap(), TokenColor::Yellow)
+            .unwrap();
+
+        return board;
+    }
+
+    // Plays the columns until the game ends, skipping full ones, and returns the side to move.
+    fn random_game(columns: Vec<usize>) -> (Board, TokenColor) {
+        let mut board: Board = Board::default();
+        let mut color: TokenColor = TokenColor::Yellow;
+
+        for column in columns {
+            if !board.is_move_legal(column) {
+                continue;
+            }
+
+            if board.make_move_and_check_win(column, color).unwrap() {
+                board.undo_move(column).unwrap();
+                break;
+            }
+
+            color = color.invert();
+        }
+
+        return (board, color);
+    }
+
+    #[test]
+    fn open_three_is_a_double_threat() {
+        let board: Board = position("33442");
+
+        assert_eq!(board.winning_moves(TokenColor::Yellow), vec![1, 5]);
+        assert_eq!(board.forced_blocks(TokenColor::Red), vec![1, 5]);
+        assert!(board.has_double_threat(TokenColor::Yellow));
+        assert!(!board.has_double_threat(TokenColor::Red));
+    }
+
+    #[test]
+    fn block_and_threat_above_it() {
+        let board: Board = position("0011226");
+
+        assert_eq!(board.winning_moves(TokenColor::Yellow), vec![3]);
+        assert!(board.winning_moves(TokenColor::Red).is_empty());
+        assert_eq!(
+            board.tactical_moves(TokenColor::Red, (0..7).collect(), true),
+            vec![3]
+        );
+        assert!(board.is_move_unsafe(3, TokenColor::Yellow));
+        assert!(!board.is_move_unsafe(3, TokenColor::Red));
+
+        // The threat of red sits on the second row from the bottom
+        assert_eq!(board.threats(TokenColor::Red), (0, 1));
+        assert_eq!(board.threats(TokenColor::Yellow), (0, 0));
+    }
+
+    #[test]
+    fn winning_move_is_the_only_candidate() {
+        let board: Board = position("010102");
+
+        assert_eq!(
+            board.tactical_moves(TokenColor::Yellow, (0..7).collect(), false),
+            vec![0]
+        );
+    }
+
+    #[test]
+    fn threats_on_own_parity_count_more() {
+        // Red moved second, so its threat on the second row favours it
+        let board: Board = position("00112266");
+
+        assert_eq!(board.threats(TokenColor::Yellow), (0, 0));
+        assert!(board.evaluate(TokenColor::Red, TokenColor::Yellow) > 0.0);
+        assert_eq!(
+            board.evaluate(TokenColor::Red, TokenColor::Yellow),
+            -board.evaluate(TokenColor::Yellow, TokenColor::Yellow)
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn winning_moves_complete_a_line(columns in prop::collection::vec(0..7usize, 0..42)) {
+            let (mut board, color) = random_game(columns);
+
+            for side in [color, color.invert()] {
+                let expected: Vec<usize> = (0..7)
+                    .filter(|&x| {
+                        if !board.is_move_legal(x) {
+                            return false;
+                        }
+
+                        let won: bool = board.make_move_and_check_win(x, side).unwrap();
+                        board.undo_move(x).unwrap();
+
+                        return won;
+                    })
+                    .collect();
+
+                prop_assert_eq!(board.winning_moves(side), expected);
+            }
+        }
+
+        #[test]
+        fn double_threat_cannot_be_stopped(columns in prop::collection::vec(0..7usize, 0..42)) {
+            let (mut board, color) = random_game(columns);
+            let opponent: TokenColor = color.invert();
+
+            if !board.winning_moves(color).is_empty() || !board.has_double_threat(opponent) {
+                return Ok(());
+            }
+
+            for column in 0..7 {
+                if !board.is_move_legal(column) {
+                    continue;
+                }
+
+                board.make_move(column, color).unwrap();
+                prop_assert_eq!(board.get_status(), GameStatus::InProgress);
+                prop_assert!(!board.winning_moves(opponent).is_empty());
+                board.undo_move(column).unwrap();
+            }
+        }
+    }
+}
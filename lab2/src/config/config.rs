@@ -0,0 +1,333 @@
+use crate::{
+    board::board::Variant,
+    node::{backup::Backup, ordering::OrderingOptions},
+    process::{level::Level, player::Player},
+};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interface {
+    Plain,
+    Terminal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameKind {
+    ConnectFour,
+    TicTacToe,
+}
+
+impl GameKind {
+    pub fn parse(input: &str) -> Option<GameKind> {
+        return match input {
+            "connect4" => Some(GameKind::ConnectFour),
+            "tictactoe" => Some(GameKind::TicTacToe),
+            _ => None,
+        };
+    }
+}
+
+// How the ranks share a search: the master splits the tree two plies below the root into tasks
+// for the workers, or every rank searches the whole tree and they share a transposition table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parallel {
+    Split,
+    Lazy,
+}
+
+impl Parallel {
+    pub fn parse(input: &str) -> Option<Parallel> {
+        return match input {
+            "split" => Some(Parallel::Split),
+            "lazy" => Some(Parallel::Lazy),
+            _ => None,
+        };
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub interface: Interface,
+    pub game: GameKind,
+    pub variant: Variant,
+    pub first: Player,
+    pub second: Player,
+    pub step: bool,
+    pub depth: usize,
+    pub time: Option<Duration>,
+    pub increment: Duration,
+    pub levels: Option<(usize, usize)>,
+    pub seed: Option<u64>,
+    pub gauntlet: Option<usize>,
+    pub book: Option<String>,
+    pub generate_book: Option<String>,
+    pub book_ply: usize,
+    pub ordering: OrderingOptions,
+    pub backup: Backup,
+    pub stats_json: Option<String>,
+    pub bench: bool,
+    pub bench_output: Option<String>,
+    pub suite: bool,
+    pub verify_wins: bool,
+    pub record: Option<String>,
+    pub export_tree: Option<String>,
+    pub export_depth: usize,
+    pub export_principal: bool,
+    pub parallel: Parallel,
+    // Search threads per rank in a Lazy SMP search.
+    pub threads: usize,
+    // Workers of the split search share a transposition table partitioned by hash.
+    pub distributed_table: bool,
+    // Idle workers of the split search get pieces of the tasks still running.
+    pub steal: bool,
+}
+
+// Flags that still mean something for tic-tac-toe, which the engine only plays against itself.
+const TICTACTOE_FLAGS: [&str; 9] = [
+    "--plain",
+    "--tui",
+    "--game",
+    "--ordering",
+    "--backup",
+    "--parallel",
+    "--threads",
+    "--distributed-table",
+    "--steal",
+];
+
+// Flags of the split search, Lazy SMP shares one table and splits nothing.
+const SPLIT_FLAGS: [&str; 2] = ["--distributed-table", "--steal"];
+
+impl Config {
+    pub fn from_args() -> Result<Config, String> {
+        return Self::parse(std::env::args().skip(1));
+    }
+
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Config, String> {
+        let mut config: Config = Config::default();
+        let mut flags: Vec<String> = vec![];
+
+        while let Some(arg) = args.next() {
+            flags.push(arg.clone());
+
+            match arg.as_str() {
+                "--tui" => config.interface = Interface::Terminal,
+                "--plain" => config.interface = Interface::Plain,
+                "--mode" => {
+                    let value: String = Self::value(&arg, args.next())?;
+                    let invalid = || format!("invalid game mode: {}", value);
+
+                    let (first, second) = value.split_once('-').ok_or_else(invalid)?;
+                    config.first = Player::parse(first).ok_or_else(invalid)?;
+                    config.second = Player::parse(second).ok_or_else(invalid)?;
+                }
+                "--step" => config.step = true,
+                "--depth" => config.depth = Self::number(&arg, args.next())?,
+                "--time" => config.time = Some(Self::seconds(&arg, args.next())?),
+                "--increment" => config.increment = Self::seconds(&arg, args.next())?,
+                "--level" => {
+                    let value: String = Self::value(&arg, args.next())?;
+                    let invalid = || format!("invalid level: {}", value);
+
+                    let (first, second) = value.split_once(',').unwrap_or((&value, &value));
+                    let first: usize = first.parse().map_err(|_| invalid())?;
+                    let second: usize = second.parse().map_err(|_| invalid())?;
+
+                    if Level::get(first).is_none() || Level::get(second).is_none() {
+                        return Err(invalid());
+                    }
+
+                    config.levels = Some((first, second));
+                }
+                "--seed" => config.seed = Some(Self::number(&arg, args.next())? as u64),
+                "--gauntlet" => {
+                    let games: usize = Self::number(&arg, args.next())?;
+
+                    if games == 0 {
+                        return Err("invalid value for --gauntlet: 0".to_string());
+                    }
+
+                    config.gauntlet = Some(games);
+                }
+                "--book" => config.book = Some(Self::value(&arg, args.next())?),
+                "--generate-book" => config.generate_book = Some(Self::value(&arg, args.next())?),
+                "--book-ply" => config.book_ply = Self::number(&arg, args.next())?,
+                "--bench" => config.bench = true,
+                "--suite" => config.suite = true,
+                "--verify-wins" => config.verify_wins = true,
+                "--record" => config.record = Some(Self::value(&arg, args.next())?),
+                "--bench-output" => config.bench_output = Some(Self::value(&arg, args.next())?),
+                "--export-tree" => config.export_tree = Some(Self::value(&arg, args.next())?),
+                "--export-depth" => config.export_depth = Self::number(&arg, args.next())?,
+                "--export-pv" => config.export_principal = true,
+                "--stats-json" => config.stats_json = Some(Self::value(&arg, args.next())?),
+                "--ordering" => {
+                    let value: String = Self::value(&arg, args.next())?;
+
+                    config.ordering = OrderingOptions::parse(&value)
+                        .ok_or_else(|| format!("invalid move ordering: {}", value))?;
+                }
+                "--backup" => {
+                    let value: String = Self::value(&arg, args.next())?;
+
+                    config.backup = Backup::parse(&value)
+                        .ok_or_else(|| format!("invalid backup rule: {}", value))?;
+                }
+                "--game" => {
+                    let value: String = Self::value(&arg, args.next())?;
+
+                    config.game = GameKind::parse(&value)
+                        .ok_or_else(|| format!("invalid game: {}", value))?;
+                }
+                "--variant" => {
+                    let value: String = Self::value(&arg, args.next())?;
+
+                    config.variant = Variant::parse(&value)
+                        .ok_or_else(|| format!("invalid variant: {}", value))?;
+                }
+                "--parallel" => {
+                    let value: String = Self::value(&arg, args.next())?;
+
+                    config.parallel = Parallel::parse(&value)
+                        .ok_or_else(|| format!("invalid parallel search: {}", value))?;
+                }
+                "--distributed-table" => config.distributed_table = true,
+                "--steal" => config.steal = true,
+                "--threads" => {
+                    config.threads = Self::number(&arg, args.next())?;
+
+                    if config.threads == 0 {
+                        return Err("invalid value for --threads: 0".to_string());
+                    }
+                }
+                _ => return Err(format!("unknown argument: {}", arg)),
+            }
+        }
+
+        if config.game == GameKind::TicTacToe {
+            if let Some(flag) = flags
+                .iter()
+                .find(|x| !TICTACTOE_FLAGS.contains(&x.as_str()))
+            {
+                return Err(format!("{} does not apply to tic-tac-toe", flag));
+            }
+        }
+
+        if config.parallel == Parallel::Lazy {
+            if let Some(flag) = flags.iter().find(|x| SPLIT_FLAGS.contains(&x.as_str())) {
+                return Err(format!("{} does not apply to --parallel lazy", flag));
+            }
+        }
+
+        return Ok(config);
+    }
+
+    pub fn usage() -> &'static str {
+        return "usage: lab2 [--plain | --tui] [--mode <human|engine>-<human|engine>] [--step] \
+                [--depth <plies>] [--time <seconds>] [--increment <seconds>] \
+                [--level <1-5>[,<1-5>]] [--seed <n>] [--gauntlet <games>] [--book <file>] \
+                [--generate-book <file>] [--book-ply <plies>] \
+                [--ordering <none|all|center,killer,history,table>] [--stats-json <file>] \
+                [--bench] [--bench-output <file.csv|file.json>] [--suite] \
+                [--verify-wins] [--record <file>] [--backup <minimax|expectimax|hybrid>] \
+                [--export-tree <file.dot|file.json>] [--export-depth <plies>] [--export-pv] \
+                [--game <connect4|tictactoe>] [--variant <standard|popout>] \
+                [--parallel <split|lazy>] [--threads <n>] [--distributed-table] [--steal]";
+    }
+
+    fn value(arg: &str, value: Option<String>) -> Result<String, String> {
+        return value.ok_or_else(|| format!("missing value for {}", arg));
+    }
+
+    fn number(arg: &str, value: Option<String>) -> Result<usize, String> {
+        let value: String = Self::value(arg, value)?;
+
+        return value
+            .parse()
+            .map_err(|_| format!("invalid value for {}: {}", arg, value));
+    }
+
+    fn seconds(arg: &str, value: Option<String>) -> Result<Duration, String> {
+        let value: String = Self::value(arg, value)?;
+
+        return value
+            .parse::<f64>()
+            .ok()
+            .and_then(|x| Duration::try_from_secs_f64(x).ok())
+            .ok_or_else(|| format!("invalid value for {}: {}", arg, value));
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        return Self {
+            interface: Interface::Plain,
+            game: GameKind::ConnectFour,
+            variant: Variant::Standard,
+            first: Player::Human,
+            second: Player::Engine,
+            step: false,
+            depth: 4,
+            time: None,
+            increment: Duration::ZERO,
+            levels: None,
+            seed: None,
+            gauntlet: None,
+            book: None,
+            generate_book: None,
+            book_ply: 3,
+            ordering: OrderingOptions::all(),
+            backup: Backup::default(),
+            stats_json: None,
+            bench: false,
+            bench_output: None,
+            suite: false,
+            verify_wins: false,
+            record: None,
+            export_tree: None,
+            export_depth: 3,
+            export_principal: false,
+            parallel: Parallel::Split,
+            threads: 1,
+            distributed_table: false,
+            steal: false,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Config, String> {
+        return Config::parse(args.split_whitespace().map(String::from));
+    }
+
+    #[test]
+    fn tictactoe_rejects_connect_four_flags() {
+        assert!(parse("--game tictactoe --backup minimax --threads 2 --steal").is_ok());
+        assert_eq!(
+            parse("--depth 6 --game tictactoe").unwrap_err(),
+            "--depth does not apply to tic-tac-toe"
+        );
+        assert_eq!(
+            parse("--game tictactoe --variant popout").unwrap_err(),
+            "--variant does not apply to tic-tac-toe"
+        );
+        assert!(parse("--depth 6 --variant popout").is_ok());
+    }
+
+    #[test]
+    fn lazy_rejects_split_flags() {
+        assert!(parse("--parallel lazy --threads 4").is_ok());
+        assert!(parse("--parallel split --steal --distributed-table").is_ok());
+        assert_eq!(
+            parse("--steal --parallel lazy").unwrap_err(),
+            "--steal does not apply to --parallel lazy"
+        );
+        assert_eq!(
+            parse("--parallel lazy --distributed-table").unwrap_err(),
+            "--distributed-table does not apply to --parallel lazy"
+        );
+    }
+}
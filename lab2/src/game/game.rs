@@ -0,0 +1,82 @@
+use crate::board::{
+    board::{GameStatus, MoveError},
+    token::TokenColor,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt::Debug;
+
+// What the search needs from a two player game. The search numbers the moves, the columns of
+// Connect Four or the cells of tic-tac-toe, and the game translates the numbers into its own
+// moves, like the drops and pops of PopOut. Positions and moves travel between master and
+// workers, so they have to be serializable, and to threads of a Lazy SMP search.
+pub trait Game: Clone + Debug + Send + Serialize + DeserializeOwned {
+    type Move: Copy + Debug + Serialize + DeserializeOwned;
+
+    fn decode(self: &Self, index: usize) -> Self::Move;
+
+    fn encode(self: &Self, m: Self::Move) -> usize;
+
+    // Moves `color` may play, none once the game is over or drawn.
+    fn legal_moves(self: &Self, color: TokenColor) -> Vec<usize>;
+
+    // Plays the move for `color` and returns the status after it. A move may also decide the
+    // game for the opponent, like a pop in PopOut.
+    fn apply(self: &mut Self, m: usize, color: TokenColor) -> Result<GameStatus, MoveError>;
+
+    fn undo(self: &mut Self, m: usize) -> Result<(), MoveError>;
+
+    fn status(self: &Self) -> GameStatus;
+
+    // Number of moves played.
+    fn ply(self: &Self) -> usize;
+
+    fn hash(self: &Self) -> u64;
+
+    // Same for a position and its mirror image, for games that have one.
+    fn canonical_hash(self: &Self) -> u64 {
+        return self.hash();
+    }
+
+    fn mirror(self: &Self, m: usize) -> usize {
+        return m;
+    }
+
+    fn is_symmetric(self: &Self) -> bool {
+        return false;
+    }
+
+    // Narrows the moves down to a winning move, if there is one. With `prune` the moves that
+    // lose at once may go as well, which keeps the minimax value but not a mean. The moves are
+    // kept in the given order.
+    fn tactical_moves(
+        self: &Self,
+        _color: TokenColor,
+        moves: Vec<usize>,
+        _prune: bool,
+    ) -> Vec<usize> {
+        return moves;
+    }
+
+    // Winner and ply of the winning move when the result is already certain, although the
+    // game is still in progress.
+    fn forced_result(self: &Self, _to_move: TokenColor) -> Option<(TokenColor, usize)> {
+        return None;
+    }
+
+    // Heuristic value of an undecided position for `color`, between -1 and 1.
+    fn evaluate(self: &Self, _color: TokenColor, _to_move: TokenColor) -> f64 {
+        return 0.0;
+    }
+
+    // Plays the moves alternately, starting with `first`.
+    fn play_line(self: &mut Self, moves: &[usize], first: TokenColor) -> Result<(), MoveError> {
+        let mut color: TokenColor = first;
+
+        for &m in moves {
+            self.apply(m, color)?;
+            color = color.invert();
+        }
+
+        return Ok(());
+    }
+}
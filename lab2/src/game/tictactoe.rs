@@ -0,0 +1,279 @@
+use super::game::Game;
+use crate::board::{
+    board::{GameStatus, MoveError},
+    token::TokenColor,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const SIZE: usize = 3;
+const CELLS: usize = SIZE * SIZE;
+
+const LINES: [[usize; 3]; 8] = [
+    [0, 1, 2],
+    [3, 4, 5],
+    [6, 7, 8],
+    [0, 3, 6],
+    [1, 4, 7],
+    [2, 5, 8],
+    [0, 4, 8],
+    [2, 4, 6],
+];
+
+// Tic-tac-toe on a 3x3 grid, moves are the cells numbered row by row from the top left. Small
+// enough to be searched to the end, which makes it a check of the search itself: perfect play
+// from the empty grid is a draw.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TicTacToe {
+    cells: [Option<TokenColor>; CELLS],
+}
+
+impl TicTacToe {
+    fn winner(self: &Self) -> Option<TokenColor> {
+        return LINES.iter().find_map(|line| {
+            let color: TokenColor = self.cells[line[0]]?;
+
+            return match line.iter().all(|&x| self.cells[x] == Some(color)) {
+                true => Some(color),
+                false => None,
+            };
+        });
+    }
+
+    // Cells in which `color` completes a line right away.
+    fn winning_moves(self: &Self, color: TokenColor) -> Vec<usize> {
+        return (0..CELLS)
+            .filter(|&cell| {
+                self.cells[cell].is_none()
+                    && LINES.iter().any(|line| {
+                        line.contains(&cell)
+                            && line
+                                .iter()
+                                .all(|&x| x == cell || self.cells[x] == Some(color))
+                    })
+            })
+            .collect();
+    }
+
+    fn hash_of(cells: impl Iterator<Item = Option<TokenColor>>) -> u64 {
+        return cells.fold(0, |hash, x| {
+            hash * 3
+                + match x {
+                    None => 0,
+                    Some(TokenColor::Yellow) => 1,
+                    Some(TokenColor::Red) => 2,
+                }
+        });
+    }
+}
+
+impl Game for TicTacToe {
+    type Move = usize;
+
+    fn decode(self: &Self, index: usize) -> usize {
+        return index;
+    }
+
+    fn encode(self: &Self, cell: usize) -> usize {
+        return cell;
+    }
+
+    fn legal_moves(self: &Self, _color: TokenColor) -> Vec<usize> {
+        if self.winner().is_some() {
+            return vec![];
+        }
+
+        return (0..CELLS).filter(|&x| self.cells[x].is_none()).collect();
+    }
+
+    fn apply(self: &mut Self, cell: usize, color: TokenColor) -> Result<GameStatus, MoveError> {
+        if cell >= CELLS {
+            return Err(MoveError::CellOutOfRange(cell));
+        }
+
+        if self.cells[cell].is_some() {
+            return Err(MoveError::Occupied(cell));
+        }
+
+        self.cells[cell] = Some(color);
+
+        return Ok(self.status());
+    }
+
+    fn undo(self: &mut Self, cell: usize) -> Result<(), MoveError> {
+        if cell >= CELLS {
+            return Err(MoveError::CellOutOfRange(cell));
+        }
+
+        if self.cells[cell].take().is_none() {
+            return Err(MoveError::EmptyCell(cell));
+        }
+
+        return Ok(());
+    }
+
+    fn status(self: &Self) -> GameStatus {
+        return match self.winner() {
+            Some(color) => GameStatus::Finished(color),
+            None => GameStatus::InProgress,
+        };
+    }
+
+    fn ply(self: &Self) -> usize {
+        return self.cells.iter().filter(|x| x.is_some()).count();
+    }
+
+    // The cells read as a number in base 3, which is small enough to be exact.
+    fn hash(self: &Self) -> u64 {
+        return Self::hash_of(self.cells.iter().copied());
+    }
+
+    fn canonical_hash(self: &Self) -> u64 {
+        let mirrored = (0..CELLS).map(|x| self.cells[self.mirror(x)]);
+
+        return self.hash().min(Self::hash_of(mirrored));
+    }
+
+    // Reflects the cell about the middle column.
+    fn mirror(self: &Self, cell: usize) -> usize {
+        return cell - cell % SIZE + SIZE - 1 - cell % SIZE;
+    }
+
+    fn is_symmetric(self: &Self) -> bool {
+        return (0..CELLS).all(|x| self.cells[x] == self.cells[self.mirror(x)]);
+    }
+
+    fn tactical_moves(
+        self: &Self,
+        color: TokenColor,
+        cells: Vec<usize>,
+        prune: bool,
+    ) -> Vec<usize> {
+        let wins: Vec<usize> = self.winning_moves(color);
+
+        if let Some(&cell) = cells.iter().find(|x| wins.contains(x)) {
+            return vec![cell];
+        }
+
+        if !prune {
+            return cells;
+        }
+
+        let blocks: Vec<usize> = self.winning_moves(color.invert());
+
+        if !blocks.is_empty() {
+            return cells.into_iter().filter(|x| blocks.contains(x)).collect();
+        }
+
+        return cells;
+    }
+
+    fn forced_result(self: &Self, to_move: TokenColor) -> Option<(TokenColor, usize)> {
+        if !self.winning_moves(to_move).is_empty() {
+            return Some((to_move, self.ply() + 1));
+        }
+
+        if self.winning_moves(to_move.invert()).len() >= 2 {
+            return Some((to_move.invert(), self.ply() + 2));
+        }
+
+        return None;
+    }
+}
+
+impl fmt::Display for TicTacToe {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.cells.chunks(SIZE) {
+            let line: String = row
+                .iter()
+                .map(|x| match x {
+                    None => '.',
+                    Some(TokenColor::Yellow) => 'X',
+                    Some(TokenColor::Red) => 'O',
+                })
+                .collect();
+
+            writeln!(f, "{}", line)?;
+        }
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::{
+        backup::Backup,
+        node::Node,
+        ordering::{MoveOrdering, OrderingOptions},
+        score::{win_value, NEUTRAL_VALUE},
+        stats::SearchStats,
+    };
+
+    const CPU: TokenColor = TokenColor::Yellow;
+
+    fn solve(game: &mut TicTacToe, cpu: TokenColor) -> Node {
+        let mut root: Node = Node::new(cpu.invert(), 0, GameStatus::InProgress);
+        root.search(
+            game,
+            CELLS,
+            0,
+            cpu,
+            Backup::Minimax,
+            &mut MoveOrdering::new(OrderingOptions::all()),
+            &mut SearchStats::default(),
+        );
+
+        return root;
+    }
+
+    #[test]
+    fn perfect_play_is_a_draw() {
+        let root: Node = solve(&mut TicTacToe::default(), CPU);
+
+        assert_eq!(root.value, Some(NEUTRAL_VALUE));
+        assert_eq!(root.children.len(), CELLS);
+    }
+
+    #[test]
+    fn completes_the_row() {
+        let mut game: TicTacToe = TicTacToe::default();
+        game.play_line(&[0, 3, 1, 4], CPU).unwrap();
+
+        let root: Node = solve(&mut game, CPU);
+
+        assert_eq!(root.best_child(CPU).unwrap().column, 2);
+        assert_eq!(root.value, Some(win_value(5)));
+        assert_eq!(game.to_string(), "XX.\nOO.\n...\n");
+    }
+
+    #[test]
+    fn moves_are_checked() {
+        let mut game: TicTacToe = TicTacToe::default();
+
+        assert_eq!(game.apply(4, CPU), Ok(GameStatus::InProgress));
+        assert_eq!(game.apply(4, CPU.invert()), Err(MoveError::Occupied(4)));
+        assert_eq!(game.apply(9, CPU), Err(MoveError::CellOutOfRange(9)));
+        assert_eq!(game.undo(0), Err(MoveError::EmptyCell(0)));
+
+        game.undo(4).unwrap();
+        assert_eq!(game, TicTacToe::default());
+    }
+
+    #[test]
+    fn mirrored_positions_share_a_hash() {
+        let mut left: TicTacToe = TicTacToe::default();
+        let mut right: TicTacToe = TicTacToe::default();
+        left.play_line(&[0, 4, 3], CPU).unwrap();
+        right.play_line(&[2, 4, 5], CPU).unwrap();
+
+        assert_ne!(left.hash(), right.hash());
+        assert_eq!(left.canonical_hash(), right.canonical_hash());
+        assert!(!left.is_symmetric());
+
+        left.apply(5, CPU.invert()).unwrap();
+        right.apply(3, CPU.invert()).unwrap();
+        assert_eq!(left.canonical_hash(), right.canonical_hash());
+    }
+}
@@ -1,26 +1,115 @@
 mod board;
+mod config;
+mod game;
 mod node;
 mod process;
+mod ui;
 
 use board::board::Board;
-use mpi::traits::*;
+use config::config::{Config, GameKind, Interface, Parallel};
+use game::tictactoe::TicTacToe;
+use mpi::{traits::*, Threading};
+use process::error::ProcessError;
 use process::master::Master;
 use process::worker::Worker;
+use ui::{plain::PlainUi, terminal::TerminalUi, ui::Ui};
 
 fn main() {
-    let universe = mpi::initialize().unwrap();
+    // Only the main thread of a rank calls MPI, the threads of a Lazy SMP search just search.
+    let (universe, threading) = mpi::initialize_with_threading(Threading::Funneled).unwrap();
     let world = universe.world();
     let rank: i32 = world.rank();
     let master_rank: i32 = 0;
 
-    let board = Board::default();
+    let config: Config = match Config::from_args() {
+        Ok(config) => config,
+        Err(e) => {
+            if rank == master_rank {
+                eprintln!("{}\n{}", e, Config::usage());
+            }
+            return;
+        }
+    };
 
-    let mut master = Master::new(world, board);
-    let worker: Worker = Worker::new(world, master_rank, 4);
+    if config.parallel == Parallel::Lazy && threading < Threading::Funneled {
+        if rank == master_rank {
+            eprintln!("the MPI library does not support the threads of a Lazy SMP search");
+        }
+        return;
+    }
 
     if rank == master_rank {
-        master.run();
+        let ui: Box<dyn Ui> = match config.interface {
+            Interface::Plain => Box::new(PlainUi::new()),
+            Interface::Terminal => match TerminalUi::new() {
+                Ok(ui) => Box::new(ui),
+                Err(e) => {
+                    eprintln!(
+                        "cannot start the terminal interface, using plain output: {}",
+                        e
+                    );
+                    Box::new(PlainUi::new())
+                }
+            },
+        };
+
+        let mut board: Board = Board::default();
+        board.set_verify(config.verify_wins);
+        board.set_variant(config.variant);
+
+        let mut master = Master::new(world, board, ui, &config);
+        if let Some(path) = &config.book {
+            if let Err(e) = master.load_book(path) {
+                eprintln!("{}", e);
+                master.notify_game_finished();
+                return;
+            }
+        }
+
+        if let Some(path) = &config.stats_json {
+            if let Err(e) = master.open_stats(path) {
+                eprintln!("{}", e);
+                master.notify_game_finished();
+                return;
+            }
+        }
+
+        let result: Result<(), ProcessError> = if config.game == GameKind::TicTacToe {
+            master.run_tictactoe()
+        } else if let Some(path) = &config.generate_book {
+            master.generate_book(path, config.book_ply)
+        } else if config.suite {
+            master.run_suite()
+        } else if config.bench {
+            master.run_benchmark(config.bench_output.as_deref())
+        } else if let Some(games) = config.gauntlet {
+            master.run_gauntlet(games)
+        } else {
+            master.run()
+        };
+
+        master.notify_game_finished();
+        // Leaves the terminal interface before the error is printed
+        drop(master);
+
+        if let Err(e) = result {
+            eprintln!("error: {}", e);
+        }
     } else {
-        worker.run();
+        let mut worker: Worker = Worker::new(
+            world,
+            master_rank,
+            config.ordering,
+            config.distributed_table,
+        );
+
+        let result: Result<(), ProcessError> = match config.game {
+            GameKind::ConnectFour => worker.run::<Board>(),
+            GameKind::TicTacToe => worker.run::<TicTacToe>(),
+        };
+
+        if let Err(e) = result {
+            eprintln!("error on rank {}: {}", rank, e);
+        }
     }
 }
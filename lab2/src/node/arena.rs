@@ -0,0 +1,580 @@
+use super::{
+    backup::Backup,
+    node::{leaf_value, Node},
+};
+use crate::board::board::GameStatus;
+use crate::board::token::TokenColor;
+use crate::game::game::Game;
+use serde::{Deserialize, Serialize};
+use std::mem;
+
+pub type NodeId = usize;
+
+pub const ROOT: NodeId = 0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreeNode {
+    pub color: TokenColor,
+    pub column: usize,
+    pub value: Option<f64>,
+    pub status: GameStatus,
+    pub parent: Option<NodeId>,
+    pub children: Vec<NodeId>,
+}
+
+// Search tree with all nodes in one vector, linked by their index. Children always come after
+// their parent. Grafting replaces the children of a node with a copy appended at the end, the
+// nodes it replaced stay allocated until the tree is dropped. Merging keeps them and adds the
+// ones that are new, like the result of a task next to the pieces split off from it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tree {
+    nodes: Vec<TreeNode>,
+}
+
+impl Tree {
+    pub fn from_node(node: &Node) -> Tree {
+        let mut tree: Tree = Self { nodes: vec![] };
+        tree.push(node, None);
+
+        return tree;
+    }
+
+    fn push(self: &mut Self, node: &Node, parent: Option<NodeId>) -> NodeId {
+        let id: NodeId = self.nodes.len();
+
+        self.nodes.push(TreeNode {
+            color: node.color(),
+            column: node.column,
+            value: node.value,
+            status: node.status(),
+            parent,
+            children: vec![],
+        });
+
+        for child in node.children.iter() {
+            let child_id: NodeId = self.push(child, Some(id));
+            self.nodes[id].children.push(child_id);
+        }
+
+        return id;
+    }
+
+    pub fn node(self: &Self, id: NodeId) -> Option<&TreeNode> {
+        return self.nodes.get(id);
+    }
+
+    pub fn children(self: &Self, id: NodeId) -> &[NodeId] {
+        return &self.nodes[id].children;
+    }
+
+    pub fn len(self: &Self) -> usize {
+        return self.nodes.len();
+    }
+
+    // Columns played from the root down to the node.
+    pub fn path(self: &Self, id: NodeId) -> Vec<usize> {
+        let mut path: Vec<usize> = vec![];
+        let mut node: NodeId = id;
+
+        while let Some(parent) = self.nodes[node].parent {
+            path.push(self.nodes[node].column);
+            node = parent;
+        }
+
+        path.reverse();
+
+        return path;
+    }
+
+    // Copy of the subtree below the node, with the node as its root.
+    pub fn subtree(self: &Self, id: NodeId) -> Tree {
+        let mut tree: Tree = Self { nodes: vec![] };
+        let mut stack: Vec<(NodeId, Option<NodeId>)> = vec![(id, None)];
+
+        while let Some((source, parent)) = stack.pop() {
+            let copy: NodeId = tree.nodes.len();
+
+            tree.nodes.push(TreeNode {
+                parent,
+                children: vec![],
+                ..self.nodes[source].clone()
+            });
+
+            if let Some(parent) = parent {
+                tree.nodes[parent].children.push(copy);
+            }
+
+            // Reversed, so the children are popped and linked in their original order
+            for &child in self.nodes[source].children.iter().rev() {
+                stack.push((child, Some(copy)));
+            }
+        }
+
+        return tree;
+    }
+
+    // Materializes the subtree below the node, as the workers search it.
+    pub fn to_node(self: &Self, id: NodeId) -> Node {
+        let source: &TreeNode = &self.nodes[id];
+
+        let mut node: Node = Node::new(source.color, source.column, source.status);
+        node.value = source.value;
+        node.children = source.children.iter().map(|&x| self.to_node(x)).collect();
+
+        return node;
+    }
+
+    // Reflects every move, about the center column on a board.
+    pub fn mirror<G: Game>(self: &mut Self, game: &G) {
+        for node in self.nodes.iter_mut() {
+            node.column = game.mirror(node.column);
+            node.children.reverse();
+        }
+    }
+
+    // Puts the root of the other tree in place of the node, which keeps its column and parent.
+    pub fn graft(self: &mut Self, id: NodeId, tree: &Tree) {
+        let offset: usize = self.nodes.len() - 1;
+
+        for node in tree.nodes.iter().skip(1) {
+            self.nodes.push(TreeNode {
+                parent: node.parent.map(|x| if x == ROOT { id } else { x + offset }),
+                children: node.children.iter().map(|x| x + offset).collect(),
+                ..node.clone()
+            });
+        }
+
+        let root: &TreeNode = &tree.nodes[ROOT];
+        let target: &mut TreeNode = &mut self.nodes[id];
+
+        target.value = root.value;
+        target.status = root.status;
+        target.children = root.children.iter().map(|x| x + offset).collect();
+    }
+
+    // Like graft, but children the node already has are kept and merged with the child of the
+    // other tree for the same column, if there is one.
+    pub fn merge(self: &mut Self, id: NodeId, tree: &Tree) {
+        self.merge_node(id, tree, ROOT);
+    }
+
+    fn merge_node(self: &mut Self, id: NodeId, tree: &Tree, source: NodeId) {
+        self.nodes[id].value = tree.nodes[source].value;
+        self.nodes[id].status = tree.nodes[source].status;
+
+        for &child in tree.nodes[source].children.iter() {
+            let column: usize = tree.nodes[child].column;
+            match self.child(id, column) {
+                Some(existing) => self.merge_node(existing, tree, child),
+                None => {
+                    let copy: NodeId = self.push_childless(id, &tree.nodes[child]);
+                    self.graft(copy, &tree.subtree(child));
+                }
+            }
+        }
+    }
+
+    // New child of the node, without children of its own.
+    fn push_childless(self: &mut Self, parent: NodeId, node: &TreeNode) -> NodeId {
+        let id: NodeId = self.nodes.len();
+
+        self.nodes.push(TreeNode {
+            parent: Some(parent),
+            children: vec![],
+            ..node.clone()
+        });
+        self.nodes[parent].children.push(id);
+
+        return id;
+    }
+
+    // The child of the node for the column.
+    pub fn child(self: &Self, id: NodeId, column: usize) -> Option<NodeId> {
+        return self.nodes[id]
+            .children
+            .iter()
+            .copied()
+            .find(|&x| self.nodes[x].column == column);
+    }
+
+    pub fn add_child(self: &mut Self, parent: NodeId, node: &Node) -> NodeId {
+        let id: NodeId = self.push(node, Some(parent));
+        self.nodes[parent].children.push(id);
+
+        return id;
+    }
+
+    // Bytes held by the tree, including nodes that were replaced by a graft.
+    pub fn memory(self: &Self) -> usize {
+        return mem::size_of::<Tree>()
+            + self.nodes.capacity() * mem::size_of::<TreeNode>()
+            + self
+                .nodes
+                .iter()
+                .map(|x| x.children.capacity() * mem::size_of::<NodeId>())
+                .sum::<usize>();
+    }
+
+    // Values the nodes bottom up, with the moves into them played on the board. Nodes that
+    // already have a value, like grafted worker results, are kept.
+    pub fn calculate_value<G: Game>(
+        self: &mut Self,
+        id: NodeId,
+        game: &mut G,
+        cpu_color: TokenColor,
+        rule: Backup,
+    ) {
+        let node: &TreeNode = &self.nodes[id];
+
+        if node.children.len() == 0 {
+            self.nodes[id].value = Some(leaf_value(game, node.color, node.status, cpu_color));
+            return;
+        }
+
+        for child in node.children.clone() {
+            if self.nodes[child].value.is_some() {
+                continue;
+            }
+
+            let (column, color) = (self.nodes[child].column, self.nodes[child].color);
+
+            game.apply(column, color).unwrap();
+            self.calculate_value(child, game, cpu_color, rule);
+            game.undo(column).unwrap();
+        }
+
+        let node: &TreeNode = &self.nodes[id];
+        let values: Vec<f64> = node
+            .children
+            .iter()
+            .map(|&x| self.nodes[x].value.unwrap())
+            .collect();
+
+        self.nodes[id].value = Some(rule.combine(node.color.invert() == cpu_color, &values));
+    }
+
+    // Same choice as Node::best_child.
+    pub fn best_child(self: &Self, id: NodeId, cpu_color: TokenColor) -> Option<NodeId> {
+        let sign: f64 = match self.nodes[id].color.invert() == cpu_color {
+            true => 1.0,
+            false => -1.0,
+        };
+
+        let mut best: Option<NodeId> = None;
+
+        for &child in self.nodes[id].children.iter() {
+            let Some(value) = self.nodes[child].value else {
+                continue;
+            };
+
+            match best {
+                Some(node) if sign * value <= sign * self.nodes[node].value.unwrap() => (),
+                _ => best = Some(child),
+            }
+        }
+
+        return best;
+    }
+
+    pub fn principal_variation(self: &Self, cpu_color: TokenColor) -> Vec<usize> {
+        let mut line: Vec<usize> = vec![];
+        let mut node: NodeId = ROOT;
+
+        while let Some(child) = self.best_child(node, cpu_color) {
+            line.push(self.nodes[child].column);
+            node = child;
+        }
+
+        return line;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::board::Board;
+    use crate::node::node::{Piece, SplitPoints};
+    use crate::node::ordering::{MoveOrdering, OrderingOptions};
+    use crate::node::stats::SearchStats;
+
+    const CPU: TokenColor = TokenColor::Yellow;
+
+    // The root and two plies below it, the way the master expands a position.
+    fn expanded(board: &mut Board) -> Tree {
+        let mut root: Node = Node::new(CPU.invert(), 0, GameStatus::InProgress);
+        root.build_tree(
+            board,
+            1,
+            0,
+            CPU,
+            Backup::Minimax,
+            &MoveOrdering::new(OrderingOptions::default()),
+        );
+
+        return Tree::from_node(&root);
+    }
+
+    fn same(a: &Node, b: &Node) -> bool {
+        return bincode::serialize(a).unwrap() == bincode::serialize(b).unwrap();
+    }
+
+    #[test]
+    fn nodes_link_to_their_parents() {
+        let tree: Tree = expanded(&mut Board::default());
+
+        assert_eq!(tree.len(), 1 + 7 + 49);
+        assert_eq!(tree.node(ROOT).unwrap().parent, None);
+
+        for &child in tree.children(ROOT) {
+            for &grandchild in tree.children(child) {
+                assert!(grandchild > child);
+                assert_eq!(tree.node(grandchild).unwrap().parent, Some(child));
+                assert_eq!(
+                    tree.path(grandchild),
+                    vec![
+                        tree.node(child).unwrap().column,
+                        tree.node(grandchild).unwrap().column
+                    ]
+                );
+            }
+        }
+
+        assert!(same(&tree.to_node(ROOT), &tree.subtree(ROOT).to_node(ROOT)));
+    }
+
+    #[test]
+    fn graft_worker_result() {
+        let mut board: Board = Board::default();
+        let mut tree: Tree = expanded(&mut board);
+        let memory: usize = tree.memory();
+
+        let target: NodeId = tree.children(tree.children(ROOT)[3])[2];
+        let path: Vec<usize> = tree.path(target);
+        board.play(&path, CPU).unwrap();
+
+        let mut result: Node = tree.to_node(target);
+        result.search(
+            &mut board,
+            1,
+            0,
+            CPU,
+            Backup::Minimax,
+            &mut MoveOrdering::new(OrderingOptions::default()),
+            &mut SearchStats::default(),
+        );
+
+        let size: usize = tree.len();
+        tree.graft(target, &Tree::from_node(&result));
+
+        assert_eq!(tree.node(target).unwrap().value, result.value);
+        assert_eq!(tree.node(target).unwrap().column, 2);
+        assert!(tree.len() > size && tree.memory() > memory);
+        assert!(same(&tree.to_node(target), &result));
+
+        // Every grafted node hangs below the target
+        for id in size..tree.len() {
+            let parent: NodeId = tree.node(id).unwrap().parent.unwrap();
+
+            assert!(parent == target || parent >= size);
+            assert!(tree.path(id).starts_with(&path));
+        }
+    }
+
+    // Searches the node and splits off `count` pieces at the first chance after `after` moves.
+    fn search_split(
+        node: &mut Node,
+        board: &mut Board,
+        depth: usize,
+        after: usize,
+        count: usize,
+    ) -> Vec<Piece> {
+        let mut moves: usize = 0;
+        let mut pieces: Vec<Piece> = vec![];
+
+        node.search_split(
+            board,
+            depth,
+            0,
+            CPU,
+            Backup::Minimax,
+            &mut MoveOrdering::new(OrderingOptions::default()),
+            &mut SearchStats::default(),
+            &mut SplitPoints::default(),
+            &mut |points| {
+                moves += 1;
+
+                if moves > after && pieces.is_empty() {
+                    pieces = points.take(count, depth);
+                }
+            },
+        );
+
+        return pieces;
+    }
+
+    // Searches the pieces the way the workers do and puts them together with the rest of the
+    // task, below the node of the task, which is on the board.
+    fn assemble(
+        tree: &mut Tree,
+        target: NodeId,
+        board: &mut Board,
+        depth: usize,
+        part: &Node,
+        pieces: Vec<Piece>,
+    ) {
+        for piece in pieces {
+            let mut node: NodeId = target;
+            let mut color: TokenColor = tree.node(target).unwrap().color;
+
+            for &column in piece.path.iter() {
+                color = color.invert();
+                node = match tree.child(node, column) {
+                    Some(child) => child,
+                    None => tree.add_child(node, &Node::new(color, column, GameStatus::InProgress)),
+                };
+            }
+
+            let first: TokenColor = tree.node(target).unwrap().color.invert();
+            board.play(&piece.path, first).unwrap();
+            let status: GameStatus = board.apply(piece.column, piece.color).unwrap();
+
+            let mut result: Node = Node::new(piece.color, piece.column, status);
+            assert!(
+                search_split(&mut result, board, depth - piece.path.len() - 1, 0, 0).is_empty()
+            );
+
+            board.undo(piece.column).unwrap();
+            for &column in piece.path.iter().rev() {
+                board.undo_move(column).unwrap();
+            }
+
+            let id: NodeId = tree.add_child(node, &Node::new(piece.color, piece.column, status));
+            tree.merge(id, &Tree::from_node(&result));
+        }
+
+        tree.merge(target, &Tree::from_node(part));
+    }
+
+    // A task split after two moves, with the other moves searched as pieces of their own,
+    // adds up to the value of the whole task.
+    #[test]
+    fn split_task_matches_whole_task() {
+        let mut board: Board = Board::default();
+        let mut tree: Tree = expanded(&mut board);
+        let target: NodeId = tree.children(tree.children(ROOT)[2])[4];
+        let node: Node = tree.to_node(target);
+        board.play(&tree.path(target), CPU).unwrap();
+
+        let mut whole: Node = node.clone();
+        assert!(search_split(&mut whole, &mut board, 2, usize::MAX, 0).is_empty());
+
+        let mut part: Node = node.clone();
+        let pieces: Vec<Piece> = search_split(&mut part, &mut board, 2, 2, 3);
+        assert_eq!(pieces.len(), 3);
+        assert!(pieces.iter().all(|x| x.path.is_empty()));
+        assert_eq!(part.value, None);
+
+        assemble(&mut tree, target, &mut board, 2, &part, pieces);
+        assert_eq!(tree.children(target).len(), 7);
+
+        tree.calculate_value(ROOT, &mut Board::default(), CPU, Backup::Minimax);
+
+        assert_eq!(tree.node(target).unwrap().value, whole.value);
+    }
+
+    // With forced moves at the top of the task the pieces come from the nodes below them.
+    #[test]
+    fn split_below_the_top_node() {
+        // Red has to block in column 3
+        let mut board: Board = Board::default();
+        board.play(&[6, 0, 6, 1, 5, 2], CPU.invert()).unwrap();
+
+        let node: Node = Node::new(CPU, 2, GameStatus::InProgress);
+        let mut tree: Tree = Tree::from_node(&node);
+
+        let mut whole: Node = node.clone();
+        search_split(&mut whole, &mut board, 3, usize::MAX, 0);
+
+        let mut part: Node = node.clone();
+        let pieces: Vec<Piece> = search_split(&mut part, &mut board, 3, 0, 4);
+        assert_eq!(pieces.len(), 4);
+        assert!(pieces.iter().all(|x| x.path.len() == 2 && x.path[0] == 3));
+        assert_eq!(part.value, None);
+
+        assemble(&mut tree, ROOT, &mut board, 3, &part, pieces);
+        assert_eq!(tree.children(ROOT).len(), 1);
+
+        tree.calculate_value(ROOT, &mut board, CPU, Backup::Minimax);
+
+        assert_eq!(tree.node(ROOT).unwrap().value, whole.value);
+    }
+
+    // Grafting onto a node that already has children replaces them, merging keeps them.
+    #[test]
+    fn graft_and_merge_inner_node() {
+        let mut tree: Tree = expanded(&mut Board::default());
+        let inner: NodeId = tree.children(ROOT)[3];
+        let other: Tree = tree.subtree(tree.children(ROOT)[4]);
+
+        let size: usize = tree.len();
+        tree.merge(inner, &tree.subtree(inner));
+        assert_eq!(tree.len(), size);
+        assert_eq!(tree.children(inner).len(), 7);
+
+        tree.graft(inner, &other);
+        assert_eq!(tree.children(inner).len(), 7);
+        assert!(tree.children(inner).iter().all(|&x| x >= size));
+        assert_eq!(tree.node(inner).unwrap().column, 3);
+        assert!(same(
+            &tree.to_node(inner).children[0],
+            &other.to_node(ROOT).children[0]
+        ));
+    }
+
+    #[test]
+    fn subtree_survives_serialization() {
+        let tree: Tree = expanded(&mut Board::default());
+        let child: NodeId = tree.children(ROOT)[1];
+
+        let subtree: Tree = tree.subtree(child);
+        let copy: Tree = bincode::deserialize(&bincode::serialize(&subtree).unwrap()).unwrap();
+
+        assert_eq!(copy.len(), 8);
+        assert!(same(&copy.to_node(ROOT), &tree.to_node(child)));
+        assert_eq!(copy.node(ROOT).unwrap().parent, None);
+    }
+
+    #[test]
+    fn mirrored_subtree_matches_mirrored_node() {
+        let tree: Tree = expanded(&mut Board::default());
+        let child: NodeId = tree.children(ROOT)[5];
+
+        let mut subtree: Tree = tree.subtree(child);
+        subtree.mirror(&Board::default());
+
+        assert!(same(
+            &subtree.to_node(ROOT),
+            &tree.to_node(child).mirrored(&Board::default())
+        ));
+    }
+
+    // The children of a column on the empty board are copied from the mirror column, which
+    // puts them in the mirrored order instead of the order of the move ordering.
+    #[test]
+    fn build_tree_mirrors_columns() {
+        let mut board: Board = Board::default();
+        let mut root: Node = Node::new(CPU.invert(), 0, GameStatus::InProgress);
+        root.build_tree(
+            &mut board,
+            1,
+            0,
+            CPU,
+            Backup::Minimax,
+            &MoveOrdering::new(OrderingOptions::all()),
+        );
+
+        let child = |column: usize| root.children.iter().find(|x| x.column == column).unwrap();
+
+        assert!(same(child(4), &child(2).mirrored(&board)));
+    }
+}
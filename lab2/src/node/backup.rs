@@ -0,0 +1,441 @@
+use super::score::{clamp, is_loss, is_win};
+use serde::{Deserialize, Serialize};
+
+// How the values of the children are combined into the value of their parent. Values are
+// always seen from the cpu's side, see score.rs for how proven wins and losses are scored.
+//
+// Minimax: the side to move picks its best child, the cpu the maximum and the player the
+// minimum. The value is exact for the searched depth, including how soon a result comes.
+//
+// Expectimax: the cpu picks the maximum, the player is assumed to choose uniformly at random,
+// so its nodes get the mean of their children. Only when all of them are wins or all are
+// losses is the result proven, and the player then delays it as long as possible.
+//
+// Hybrid: the original rule. A cpu win among the cpu's moves or a loss among the player's
+// decides the node, taking the fastest one, as do children that are all wins or all losses,
+// taking the slowest one. Everything else, on both sides, gets the mean of the children.
+//
+// Means count proven results as plain wins and losses, otherwise a single one would outweigh
+// everything else.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Backup {
+    Minimax,
+    Expectimax,
+    #[default]
+    Hybrid,
+}
+
+impl Backup {
+    pub fn parse(input: &str) -> Option<Backup> {
+        return match input {
+            "minimax" => Some(Backup::Minimax),
+            "expectimax" => Some(Backup::Expectimax),
+            "hybrid" => Some(Backup::Hybrid),
+            _ => None,
+        };
+    }
+
+    pub fn combine(self: &Self, cpu_to_move: bool, values: &[f64]) -> f64 {
+        let mean: f64 = values.iter().map(|&x| clamp(x)).sum::<f64>() / values.len() as f64;
+        let max: f64 = values.iter().copied().fold(f64::MIN, f64::max);
+        let min: f64 = values.iter().copied().fold(f64::MAX, f64::min);
+
+        return match self {
+            Backup::Minimax if cpu_to_move => max,
+            Backup::Minimax => min,
+            Backup::Expectimax if cpu_to_move => max,
+            Backup::Expectimax => {
+                if is_win(min) {
+                    min
+                } else if is_loss(max) {
+                    max
+                } else {
+                    mean
+                }
+            }
+            Backup::Hybrid => {
+                if cpu_to_move && is_win(max) {
+                    max
+                } else if !cpu_to_move && is_loss(min) {
+                    min
+                } else if is_win(min) {
+                    min
+                } else if is_loss(max) {
+                    max
+                } else {
+                    mean
+                }
+            }
+        };
+    }
+
+    // Whether the search may single out a winning move of the side to move, see
+    // Board::tactical_moves. That is where the win decides the node on its own.
+    pub fn takes_wins(self: &Self, cpu_to_move: bool) -> bool {
+        return *self != Backup::Expectimax || cpu_to_move;
+    }
+
+    // Whether the search may also leave out the moves that lose at once. That only leaves the
+    // value alone when the side to move picks its best child, a mean over the children changes
+    // without them.
+    pub fn prunes_tactics(self: &Self) -> bool {
+        return *self == Backup::Minimax;
+    }
+
+    // Whether a single child value already fixes the value of its parent, so the remaining
+    // siblings do not need to be searched.
+    pub fn decides(self: &Self, cpu_to_move: bool, value: f64) -> bool {
+        return match self {
+            Backup::Minimax | Backup::Hybrid => {
+                (cpu_to_move && is_win(value)) || (!cpu_to_move && is_loss(value))
+            }
+            Backup::Expectimax => cpu_to_move && is_win(value),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::board::{Board, GameStatus};
+    use crate::board::notation::parse_moves;
+    use crate::board::token::TokenColor;
+    use crate::game::game::Game;
+    use crate::node::arena::{Tree, ROOT};
+    use crate::node::node::Node;
+    use crate::node::ordering::{MoveOrdering, OrderingOptions};
+    use crate::node::score::{loss_value, win_value};
+    use crate::node::stats::SearchStats;
+
+    const RULES: [Backup; 3] = [Backup::Minimax, Backup::Expectimax, Backup::Hybrid];
+    const CPU: TokenColor = TokenColor::Yellow;
+
+    fn leaf(color: TokenColor, column: usize, value: f64) -> Node {
+        let mut node: Node = Node::new(color, column, GameStatus::InProgress);
+        node.value = Some(value);
+
+        return node;
+    }
+
+    fn inner(color: TokenColor, column: usize, children: Vec<Node>) -> Node {
+        let mut node: Node = Node::new(color, column, GameStatus::InProgress);
+        node.children = children;
+
+        return node;
+    }
+
+    // Red to move, so its children are player moves, each leaf holding the given value.
+    fn player_node(values: &[f64]) -> Node {
+        let children: Vec<Node> = values
+            .iter()
+            .enumerate()
+            .map(|(i, &value)| leaf(CPU.invert(), i, value))
+            .collect();
+
+        return inner(CPU, 0, children);
+    }
+
+    fn cpu_node(values: &[f64]) -> Node {
+        let children: Vec<Node> = values
+            .iter()
+            .enumerate()
+            .map(|(i, &value)| leaf(CPU, i, value))
+            .collect();
+
+        return inner(CPU.invert(), 0, children);
+    }
+
+    fn evaluate(node: Node, rule: Backup) -> f64 {
+        let mut tree: Tree = Tree::from_node(&node);
+        tree.calculate_value(ROOT, &mut Board::default(), CPU, rule);
+
+        return tree.node(ROOT).unwrap().value.unwrap();
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn parse_rules() {
+        assert_eq!(Backup::parse("minimax"), Some(Backup::Minimax));
+        assert_eq!(Backup::parse("expectimax"), Some(Backup::Expectimax));
+        assert_eq!(Backup::parse("hybrid"), Some(Backup::Hybrid));
+        assert_eq!(Backup::parse("average"), None);
+    }
+
+    #[test]
+    fn player_picks_refutation() {
+        let values: [f64; 3] = [0.0, loss_value(10), 0.0];
+
+        assert_close(
+            evaluate(player_node(&values), Backup::Minimax),
+            loss_value(10),
+        );
+        assert_close(
+            evaluate(player_node(&values), Backup::Expectimax),
+            -1.0 / 3.0,
+        );
+        assert_close(
+            evaluate(player_node(&values), Backup::Hybrid),
+            loss_value(10),
+        );
+    }
+
+    #[test]
+    fn cpu_avoids_loss() {
+        let values: [f64; 3] = [0.0, 0.0, loss_value(10)];
+
+        assert_close(evaluate(cpu_node(&values), Backup::Minimax), 0.0);
+        assert_close(evaluate(cpu_node(&values), Backup::Expectimax), 0.0);
+        assert_close(evaluate(cpu_node(&values), Backup::Hybrid), -1.0 / 3.0);
+    }
+
+    #[test]
+    fn cpu_takes_fastest_win() {
+        for rule in RULES {
+            let values: [f64; 4] = [0.0, win_value(11), -0.5, win_value(7)];
+
+            assert_close(evaluate(cpu_node(&values), rule), win_value(7));
+        }
+    }
+
+    #[test]
+    fn player_takes_fastest_win() {
+        let values: [f64; 3] = [loss_value(12), 0.5, loss_value(8)];
+
+        assert_close(
+            evaluate(player_node(&values), Backup::Minimax),
+            loss_value(8),
+        );
+        assert_close(evaluate(player_node(&values), Backup::Expectimax), -0.5);
+        assert_close(
+            evaluate(player_node(&values), Backup::Hybrid),
+            loss_value(8),
+        );
+    }
+
+    #[test]
+    fn uncertain_values() {
+        let values: [f64; 3] = [0.5, -0.25, 0.25];
+
+        assert_close(evaluate(cpu_node(&values), Backup::Minimax), 0.5);
+        assert_close(evaluate(cpu_node(&values), Backup::Expectimax), 0.5);
+        assert_close(evaluate(cpu_node(&values), Backup::Hybrid), 1.0 / 6.0);
+
+        assert_close(evaluate(player_node(&values), Backup::Minimax), -0.25);
+        assert_close(
+            evaluate(player_node(&values), Backup::Expectimax),
+            1.0 / 6.0,
+        );
+        assert_close(evaluate(player_node(&values), Backup::Hybrid), 1.0 / 6.0);
+    }
+
+    // The cpu chooses between a player reply that may blunder into a win and a quiet one.
+    #[test]
+    fn two_levels() {
+        let tree = || {
+            let mut risky: Node = player_node(&[win_value(9), 0.0]);
+            let mut quiet: Node = player_node(&[0.0, 0.0]);
+            risky.column = 0;
+            quiet.column = 1;
+
+            return inner(CPU.invert(), 0, vec![risky, quiet]);
+        };
+
+        assert_close(evaluate(tree(), Backup::Minimax), 0.0);
+        assert_close(evaluate(tree(), Backup::Expectimax), 0.5);
+        assert_close(evaluate(tree(), Backup::Hybrid), 0.25);
+    }
+
+    // With nothing but wins or losses to choose from the result is delayed as long as possible.
+    #[test]
+    fn decided_by_all_children() {
+        for rule in [Backup::Expectimax, Backup::Hybrid] {
+            let wins: [f64; 2] = [win_value(9), win_value(11)];
+            let losses: [f64; 2] = [loss_value(10), loss_value(12)];
+
+            assert_close(evaluate(player_node(&wins), rule), win_value(11));
+            assert_close(evaluate(cpu_node(&losses), rule), loss_value(12));
+        }
+    }
+
+    // Pruning the siblings of a deciding child must not change any value.
+    #[test]
+    fn search_matches_full_tree() {
+        for moves in ["", "3342", "332244", "33332222", "3424334422"] {
+            let mut board: Board = Board::default();
+            board
+                .play(&parse_moves(moves).unwrap(), TokenColor::Yellow)
+                .unwrap();
+
+            let cpu: TokenColor = match moves.len() % 2 {
+                0 => TokenColor::Yellow,
+                _ => TokenColor::Red,
+            };
+
+            for rule in RULES {
+                let ordering: MoveOrdering = MoveOrdering::new(OrderingOptions::default());
+
+                let mut root: Node = Node::new(cpu.invert(), 0, GameStatus::InProgress);
+                root.build_tree(&mut board, 2, 0, cpu, rule, &ordering);
+
+                let mut full: Tree = Tree::from_node(&root);
+                full.calculate_value(ROOT, &mut board, cpu, rule);
+
+                let mut searched: Node = Node::new(cpu.invert(), 0, GameStatus::InProgress);
+                searched.search(
+                    &mut board,
+                    2,
+                    0,
+                    cpu,
+                    rule,
+                    &mut MoveOrdering::new(OrderingOptions::default()),
+                    &mut SearchStats::default(),
+                );
+
+                assert_close(
+                    searched.value.unwrap(),
+                    full.node(ROOT).unwrap().value.unwrap(),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn search_takes_the_win_in_one() {
+        let mut board: Board = Board::default();
+        board
+            .play(&parse_moves("010102").unwrap(), TokenColor::Yellow)
+            .unwrap();
+
+        for rule in RULES {
+            let mut root: Node = Node::new(CPU.invert(), 0, GameStatus::InProgress);
+            root.search(
+                &mut board,
+                3,
+                0,
+                CPU,
+                rule,
+                &mut MoveOrdering::new(OrderingOptions::default()),
+                &mut SearchStats::default(),
+            );
+
+            assert_eq!(root.best_child(CPU).unwrap().column, 0);
+            assert_close(root.value.unwrap(), win_value(7));
+        }
+    }
+
+    // Every legal move, without the tactics.
+    fn unpruned(board: &mut Board, node: &mut Node, depth: usize) {
+        let color: TokenColor = node.color().invert();
+
+        for column in board.legal_moves(color) {
+            let status: GameStatus = board.apply(column, color).unwrap();
+            let mut child: Node = Node::new(color, column, status);
+
+            if depth > 0 && status == GameStatus::InProgress {
+                unpruned(board, &mut child, depth - 1);
+            }
+
+            board.undo(column).unwrap();
+            node.add_child(child);
+        }
+    }
+
+    // Leaving out the moves that lose at once keeps the minimax value, but not a mean over the
+    // children, so only minimax leaves them out.
+    #[test]
+    fn tactics_prune_only_minimax() {
+        // Red has to block in column 3, anything else lets yellow win
+        let mut board: Board = Board::default();
+        board
+            .play(&parse_moves("0011226").unwrap(), TokenColor::Yellow)
+            .unwrap();
+
+        for rule in RULES {
+            let mut root: Node = Node::new(CPU, 0, GameStatus::InProgress);
+            unpruned(&mut board, &mut root, 1);
+            let full: f64 = evaluate_at(&root, &mut board, rule);
+
+            let mut root: Node = Node::new(CPU, 0, GameStatus::InProgress);
+            root.build_tree(
+                &mut board,
+                1,
+                0,
+                CPU,
+                Backup::Minimax,
+                &MoveOrdering::new(OrderingOptions::default()),
+            );
+            let pruned: f64 = evaluate_at(&root, &mut board, rule);
+
+            let mut searched: Node = Node::new(CPU, 0, GameStatus::InProgress);
+            searched.search(
+                &mut board,
+                1,
+                0,
+                CPU,
+                rule,
+                &mut MoveOrdering::new(OrderingOptions::default()),
+                &mut SearchStats::default(),
+            );
+
+            assert_eq!(root.children.len(), 1);
+            assert_close(searched.value.unwrap(), full);
+
+            match rule.prunes_tactics() {
+                true => assert_close(pruned, full),
+                false => assert!(pruned < full),
+            }
+        }
+    }
+
+    // A winning move of the side to move decides its node under the rules that single it out,
+    // so the other moves can go without changing the value.
+    #[test]
+    fn winning_moves_narrow_where_they_decide() {
+        // Yellow wins in column 0, then red does
+        for (moves, cpu_to_move) in [("010102", true), ("6060504", false)] {
+            let mut board: Board = Board::default();
+            board
+                .play(&parse_moves(moves).unwrap(), TokenColor::Yellow)
+                .unwrap();
+
+            let last: TokenColor = match cpu_to_move {
+                true => CPU.invert(),
+                false => CPU,
+            };
+
+            for rule in RULES {
+                let mut root: Node = Node::new(last, 0, GameStatus::InProgress);
+                unpruned(&mut board, &mut root, 1);
+                let full: f64 = evaluate_at(&root, &mut board, rule);
+
+                let mut root: Node = Node::new(last, 0, GameStatus::InProgress);
+                root.build_tree(
+                    &mut board,
+                    1,
+                    0,
+                    CPU,
+                    rule,
+                    &MoveOrdering::new(OrderingOptions::default()),
+                );
+
+                assert_eq!(root.children.len() == 1, rule.takes_wins(cpu_to_move));
+                assert_close(evaluate_at(&root, &mut board, rule), full);
+            }
+        }
+    }
+
+    fn evaluate_at(node: &Node, board: &mut Board, rule: Backup) -> f64 {
+        let mut tree: Tree = Tree::from_node(node);
+        tree.calculate_value(ROOT, board, CPU, rule);
+
+        return tree.node(ROOT).unwrap().value.unwrap();
+    }
+}
@@ -0,0 +1,216 @@
+use super::{
+    arena::{NodeId, Tree, TreeNode, ROOT},
+    score::format_score,
+};
+use crate::board::{board::GameStatus, token::TokenColor};
+use serde::Serialize;
+use std::{collections::HashSet, fs, io};
+
+// Part of the master's tree written out for inspection, the root plus the nodes down to
+// `depth` plies below it. With `principal` only the principal variation and the siblings of
+// its moves are kept.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportNode {
+    pub id: NodeId,
+    pub column: Option<usize>,
+    pub color: TokenColor,
+    pub status: GameStatus,
+    pub value: Option<f64>,
+    pub score: Option<String>,
+    pub principal: bool,
+    pub children: Vec<ExportNode>,
+}
+
+impl ExportNode {
+    // `ply` is the number of tokens on the board at the root, the scores are given for the
+    // side to move there, `cpu_color`.
+    pub fn new(
+        tree: &Tree,
+        cpu_color: TokenColor,
+        ply: usize,
+        depth: usize,
+        principal: bool,
+    ) -> ExportNode {
+        let mut line: HashSet<NodeId> = HashSet::from([ROOT]);
+        let mut node: NodeId = ROOT;
+
+        while let Some(child) = tree.best_child(node, cpu_color) {
+            line.insert(child);
+            node = child;
+        }
+
+        let keep = |id: NodeId| -> bool {
+            let parent: NodeId = tree.node(id).unwrap().parent.unwrap();
+
+            return !principal || line.contains(&parent);
+        };
+
+        return Self::build(tree, ROOT, ply, depth, &line, &keep);
+    }
+
+    fn build(
+        tree: &Tree,
+        id: NodeId,
+        ply: usize,
+        depth: usize,
+        line: &HashSet<NodeId>,
+        keep: &dyn Fn(NodeId) -> bool,
+    ) -> ExportNode {
+        let node: &TreeNode = tree.node(id).unwrap();
+
+        let children: Vec<ExportNode> = match depth {
+            0 => vec![],
+            _ => tree
+                .children(id)
+                .iter()
+                .filter(|&&x| keep(x))
+                .map(|&x| Self::build(tree, x, ply, depth - 1, line, keep))
+                .collect(),
+        };
+
+        return Self {
+            id,
+            column: node.parent.map(|_| node.column),
+            color: node.color,
+            status: node.status,
+            value: node.value,
+            score: node.value.map(|x| format_score(x, ply)),
+            principal: line.contains(&id),
+            children,
+        };
+    }
+
+    pub fn to_json(self: &Self) -> String {
+        return serde_json::to_string_pretty(self).unwrap();
+    }
+
+    // Graphviz digraph, the principal variation drawn in bold and finished games with a double
+    // border.
+    pub fn to_dot(self: &Self) -> String {
+        let mut lines: Vec<String> = vec![
+            "digraph tree {".to_string(),
+            "    node [shape=box, fontname=\"monospace\"];".to_string(),
+        ];
+
+        self.write_dot(&mut lines);
+        lines.push("}".to_string());
+
+        return lines.join("\n") + "\n";
+    }
+
+    fn write_dot(self: &Self, lines: &mut Vec<String>) {
+        let mut label: Vec<String> = match self.column {
+            Some(column) => vec![format!("{} {:?}", column, self.color)],
+            None => vec![format!("root, {:?} to move", self.color.invert())],
+        };
+
+        if let GameStatus::Finished(color) = self.status {
+            label.push(format!("{:?} wins", color));
+        }
+
+        label.push(self.score.clone().unwrap_or("-".to_string()));
+
+        let mut attributes: Vec<String> = vec![format!("label=\"{}\"", label.join("\\n"))];
+
+        if self.principal {
+            attributes.push("style=bold".to_string());
+        }
+
+        if self.status != GameStatus::InProgress {
+            attributes.push("peripheries=2".to_string());
+        }
+
+        lines.push(format!("    n{} [{}];", self.id, attributes.join(", ")));
+
+        for child in self.children.iter() {
+            child.write_dot(lines);
+
+            let style: &str = match child.principal {
+                true => " [style=bold]",
+                false => "",
+            };
+
+            lines.push(format!("    n{} -> n{}{};", self.id, child.id, style));
+        }
+    }
+
+    // JSON if the path ends in .json, DOT otherwise.
+    pub fn save(self: &Self, path: &str) -> io::Result<()> {
+        if path.ends_with(".json") {
+            return fs::write(path, self.to_json());
+        }
+
+        return fs::write(path, self.to_dot());
+    }
+
+    pub fn count(self: &Self) -> usize {
+        return 1 + self.children.iter().map(|x| x.count()).sum::<usize>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::board::Board;
+    use crate::node::backup::Backup;
+    use crate::node::node::Node;
+    use crate::node::ordering::{MoveOrdering, OrderingOptions};
+
+    const CPU: TokenColor = TokenColor::Yellow;
+
+    fn searched_tree() -> Tree {
+        let mut board: Board = Board::default();
+        let mut root: Node = Node::new(CPU.invert(), 0, GameStatus::InProgress);
+        root.build_tree(
+            &mut board,
+            1,
+            0,
+            CPU,
+            Backup::Minimax,
+            &MoveOrdering::new(OrderingOptions::default()),
+        );
+
+        let mut tree: Tree = Tree::from_node(&root);
+        tree.calculate_value(ROOT, &mut board, CPU, Backup::Minimax);
+
+        return tree;
+    }
+
+    #[test]
+    fn depth_limits_the_export() {
+        let tree: Tree = searched_tree();
+
+        assert_eq!(ExportNode::new(&tree, CPU, 0, 0, false).count(), 1);
+        assert_eq!(ExportNode::new(&tree, CPU, 0, 1, false).count(), 8);
+        assert_eq!(ExportNode::new(&tree, CPU, 0, 5, false).count(), 57);
+    }
+
+    #[test]
+    fn principal_line_keeps_siblings() {
+        let tree: Tree = searched_tree();
+        let export: ExportNode = ExportNode::new(&tree, CPU, 0, 2, true);
+
+        // All moves at the root, and the replies to the best one only
+        assert_eq!(export.count(), 1 + 7 + 7);
+        assert!(export.principal);
+        assert_eq!(export.children.iter().filter(|x| x.principal).count(), 1);
+
+        for child in export.children.iter() {
+            assert_eq!(child.children.is_empty(), !child.principal);
+        }
+    }
+
+    #[test]
+    fn dot_has_an_edge_per_child() {
+        let tree: Tree = searched_tree();
+        let export: ExportNode = ExportNode::new(&tree, CPU, 0, 2, false);
+
+        let dot: String = export.to_dot();
+        assert!(dot.starts_with("digraph tree {"));
+        assert_eq!(dot.matches(" -> ").count(), export.count() - 1);
+
+        let json: serde_json::Value = serde_json::from_str(&export.to_json()).unwrap();
+        assert_eq!(json["children"].as_array().unwrap().len(), 7);
+        assert_eq!(json["column"], serde_json::Value::Null);
+    }
+}
@@ -1 +1,8 @@
+pub mod arena;
+pub mod backup;
+pub mod export;
 pub mod node;
+pub mod ordering;
+pub mod score;
+pub mod stats;
+pub mod table;
@@ -1,11 +1,14 @@
-use crate::board::board::{Board, GameStatus};
+use super::{
+    backup::Backup,
+    ordering::MoveOrdering,
+    score::{decided_at, loss_value, win_value, NEUTRAL_VALUE},
+    stats::SearchStats,
+};
+use crate::board::board::GameStatus;
 use crate::board::token::TokenColor;
+use crate::game::game::Game;
 use serde::{Deserialize, Serialize};
 
-const WIN_VALUE: f64 = 1.0;
-const LOSE_VALUE: f64 = -1.0;
-const NEUTRAL_VALUE: f64 = 0.0;
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Node {
     color: TokenColor,
@@ -26,40 +29,90 @@ impl Node {
         };
     }
 
+    pub fn color(self: &Self) -> TokenColor {
+        return self.color;
+    }
+
+    pub fn status(self: &Self) -> GameStatus {
+        return self.status;
+    }
+
     pub fn add_child(self: &mut Self, node: Node) {
         self.children.push(node)
     }
 
-    pub fn build_tree(self: &mut Self, board: &mut Board, max_depth: usize, curr_depth: usize) {
+    // Copy of the subtree with every move reflected, about the center column on a board.
+    pub fn mirrored<G: Game>(self: &Self, game: &G) -> Node {
+        return Self {
+            color: self.color,
+            column: game.mirror(self.column),
+            value: self.value,
+            children: self
+                .children
+                .iter()
+                .rev()
+                .map(|x| x.mirrored(game))
+                .collect(),
+            status: self.status,
+        };
+    }
+
+    // On a symmetric board the subtree of a column is the mirror image of the one on the other
+    // side of the center, so once one of them is built the other is copied instead of searched.
+    fn mirror_child<G: Game>(self: &mut Self, game: &G, column: usize) -> bool {
+        if column == game.mirror(column) || !game.is_symmetric() {
+            return false;
+        }
+
+        let Some(index) = self
+            .children
+            .iter()
+            .position(|x| x.column == game.mirror(column))
+        else {
+            return false;
+        };
+
+        let child: Node = self.children[index].mirrored(game);
+        self.add_child(child);
+
+        return true;
+    }
+
+    pub fn build_tree<G: Game>(
+        self: &mut Self,
+        game: &mut G,
+        max_depth: usize,
+        curr_depth: usize,
+        cpu_color: TokenColor,
+        rule: Backup,
+        ordering: &MoveOrdering,
+    ) {
         if curr_depth > max_depth {
             return;
         }
 
-        for i in 0..board.columns {
-            if !board.is_move_legal(i) {
+        let color: TokenColor = self.color.invert();
+
+        for i in candidate_moves(game, color, curr_depth, cpu_color, rule, ordering) {
+            if self.mirror_child(game, i) {
                 continue;
             }
 
-            board.make_move(i, self.color.invert()).unwrap();
-            match board.get_status() {
-                GameStatus::Finished(status) => {
-                    board.undo_move(i).unwrap();
+            match game.apply(i, color).unwrap() {
+                GameStatus::Finished(winner) => {
+                    game.undo(i).unwrap();
 
-                    self.add_child(Node::new(
-                        self.color.invert(),
-                        i,
-                        GameStatus::Finished(status),
-                    ));
+                    self.add_child(Node::new(color, i, GameStatus::Finished(winner)));
 
                     continue;
                 }
 
                 GameStatus::InProgress => {
-                    let mut child = Node::new(self.color.invert(), i, GameStatus::InProgress);
+                    let mut child = Node::new(color, i, GameStatus::InProgress);
 
-                    child.build_tree(board, max_depth, curr_depth + 1);
+                    child.build_tree(game, max_depth, curr_depth + 1, cpu_color, rule, ordering);
 
-                    board.undo_move(i).unwrap();
+                    game.undo(i).unwrap();
 
                     self.add_child(child);
                 }
@@ -67,58 +120,296 @@ impl Node {
         }
     }
 
-    pub fn calculate_value(
+    // Searches and evaluates the tree in one pass. Once a child decides the value of its parent
+    // on its own under the backup rule, like a win for the cpu among its moves, the remaining
+    // siblings only matter if they reach the same result sooner. They are searched just deep
+    // enough to find that, and not at all when the result comes with this move, so the order of
+    // the columns decides how much is pruned.
+    pub fn search<G: Game>(
         self: &mut Self,
-        board: &mut Board,
+        game: &mut G,
+        max_depth: usize,
+        curr_depth: usize,
         cpu_color: TokenColor,
-        player_color: TokenColor,
+        rule: Backup,
+        ordering: &mut MoveOrdering,
+        stats: &mut SearchStats,
     ) {
-        if self.children.len() == 0 {
-            match self.status {
-                GameStatus::Finished(color) => {
-                    if color == cpu_color {
-                        self.value = Some(WIN_VALUE);
-                    } else {
-                        self.value = Some(LOSE_VALUE);
-                    }
-                }
-                GameStatus::InProgress => self.value = Some(NEUTRAL_VALUE),
+        self.search_split(
+            game,
+            max_depth,
+            curr_depth,
+            cpu_color,
+            rule,
+            ordering,
+            stats,
+            &mut SplitPoints::default(),
+            &mut |_| (),
+        );
+    }
+
+    // Like `search`, but before each move `split` gets the moves not searched yet at every node
+    // on the way down, and may take some of them to be searched by someone else. The nodes that
+    // lost moves, and the ones above them, keep the children searched so far without a value
+    // until the pieces are put back together. Returns whether that happened below this node.
+    pub fn search_split<G: Game>(
+        self: &mut Self,
+        game: &mut G,
+        max_depth: usize,
+        curr_depth: usize,
+        cpu_color: TokenColor,
+        rule: Backup,
+        ordering: &mut MoveOrdering,
+        stats: &mut SearchStats,
+        points: &mut SplitPoints,
+        split: &mut dyn FnMut(&mut SplitPoints),
+    ) -> bool {
+        stats.nodes += 1;
+        stats.max_depth = stats.max_depth.max(curr_depth);
+
+        if curr_depth > max_depth || self.status != GameStatus::InProgress {
+            self.value = Some(leaf_value(game, self.color, self.status, cpu_color));
+            return false;
+        }
+
+        let remaining: usize = max_depth - curr_depth;
+
+        // The root is always searched, its children are the result.
+        if curr_depth > 0 {
+            if let Some(value) = ordering.probe(game, cpu_color, remaining) {
+                stats.table_hits += 1;
+                self.value = Some(value);
+                return false;
             }
+        }
 
-            return;
+        let color: TokenColor = self.color.invert();
+        let mut limit: usize = max_depth;
+        let mut moves: Vec<usize> =
+            candidate_moves(game, color, curr_depth, cpu_color, rule, ordering);
+        moves.reverse();
+
+        let level: usize = points.frames.len();
+        points.frames.push(Frame {
+            color,
+            current: 0,
+            rest: moves,
+            split: false,
+        });
+
+        while let Some(i) = points.frames[level].rest.pop() {
+            if ordering.is_stopped() {
+                break;
+            }
+
+            points.frames[level].current = i;
+            split(points);
+
+            // A child that was split has not been searched completely here
+            let split_sibling: bool = self
+                .children
+                .iter()
+                .any(|x| x.column == game.mirror(i) && x.value.is_none());
+
+            if !split_sibling && self.mirror_child(game, i) {
+                continue;
+            }
+
+            let status: GameStatus = game.apply(i, color).unwrap();
+
+            let mut child: Node = Node::new(color, i, status);
+            let child_split: bool = child.search_split(
+                game,
+                limit,
+                curr_depth + 1,
+                cpu_color,
+                rule,
+                ordering,
+                stats,
+                points,
+                split,
+            );
+
+            game.undo(i).unwrap();
+
+            points.frames[level].split |= child_split;
+
+            let Some(value) = child.value else {
+                self.add_child(child);
+                continue;
+            };
+
+            self.add_child(child);
+
+            if !rule.decides(color == cpu_color, value) {
+                continue;
+            }
+
+            ordering.record_cutoff(color, curr_depth, remaining, i);
+
+            let end: usize = decided_at(value).unwrap();
+
+            if end <= game.ply() + 1 {
+                stats.cutoffs += 1;
+                break;
+            }
+
+            // Nodes at depth d hold ply + d - curr_depth tokens and the leaves lie one below the
+            // limit, so this is the deepest limit whose leaves stop short of `end`.
+            limit = limit.min(end + curr_depth - game.ply() - 2);
+        }
+
+        if points.frames.pop().unwrap().split {
+            return true;
+        }
+
+        if self.children.len() == 0 {
+            self.value = Some(leaf_value(game, self.color, self.status, cpu_color));
+            return false;
+        }
+
+        self.backup(cpu_color, rule);
+        ordering.store_value(game, cpu_color, remaining, self.value.unwrap());
+
+        if let Some(best) = self.best_child(cpu_color) {
+            ordering.store(game, best.column);
         }
 
-        for child in self.children.iter_mut() {
-            if child.value.is_none() {
-                board.make_move(child.column, child.color).unwrap();
-                child.calculate_value(board, cpu_color, player_color);
-                board.undo_move(child.column).unwrap();
+        return false;
+    }
+
+    // The child the side to move would pick, the highest value for the cpu and the lowest
+    // for the player.
+    pub fn best_child(self: &Self, cpu_color: TokenColor) -> Option<&Node> {
+        let sign: f64 = match self.color.invert() == cpu_color {
+            true => 1.0,
+            false => -1.0,
+        };
+
+        let mut best: Option<&Node> = None;
+
+        for child in self.children.iter() {
+            let Some(value) = child.value else {
+                continue;
+            };
+
+            match best {
+                Some(node) if sign * value <= sign * node.value.unwrap() => (),
+                _ => best = Some(child),
             }
         }
 
-        if self
-            .children
-            .iter()
-            .any(|x| x.value.unwrap() == WIN_VALUE && x.color == cpu_color)
-        {
-            self.value = Some(WIN_VALUE);
-        } else if self
-            .children
-            .iter()
-            .any(|x| x.value.unwrap() == LOSE_VALUE && x.color == player_color)
-        {
-            self.value = Some(LOSE_VALUE);
-        } else if self.children.iter().all(|x| x.value.unwrap() == WIN_VALUE) {
-            self.value = Some(WIN_VALUE);
-        } else if self.children.iter().all(|x| x.value.unwrap() == LOSE_VALUE) {
-            self.value = Some(LOSE_VALUE);
-        } else {
-            self.value = Some(
-                self.children.iter().map(|x| x.value.unwrap()).sum::<f64>()
-                    / (self.children.len() as f64),
-            )
+        return best;
+    }
+
+    fn backup(self: &mut Self, cpu_color: TokenColor, rule: Backup) {
+        let values: Vec<f64> = self.children.iter().map(|x| x.value.unwrap()).collect();
+
+        self.value = Some(rule.combine(self.color.invert() == cpu_color, &values));
+    }
+}
+
+// The nodes of a search_split from its top down to the node being searched, with the moves
+// they have not searched yet. Splitting takes those moves from the highest nodes first, where
+// they lead to the largest pieces.
+#[derive(Debug, Default)]
+pub struct SplitPoints {
+    frames: Vec<Frame>,
+}
+
+#[derive(Debug)]
+struct Frame {
+    color: TokenColor,
+    // The move searched below the node right now.
+    current: usize,
+    // Moves not searched yet, the next one last.
+    rest: Vec<usize>,
+    // Moves were taken from the node or from one below it.
+    split: bool,
+}
+
+// A move split off from a search, `color` plays it after the moves in `path` from the top.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Piece {
+    pub path: Vec<usize>,
+    pub color: TokenColor,
+    pub column: usize,
+}
+
+impl SplitPoints {
+    // Takes up to `count` moves from the nodes less than `levels` plies below the top, the
+    // ones that would be searched last at each node.
+    pub fn take(self: &mut Self, mut count: usize, levels: usize) -> Vec<Piece> {
+        let mut pieces: Vec<Piece> = vec![];
+
+        for level in 0..self.frames.len().min(levels) {
+            let taken: usize = self.frames[level].rest.len().min(count);
+
+            if taken == 0 {
+                continue;
+            }
+
+            let path: Vec<usize> = self.frames[..level].iter().map(|x| x.current).collect();
+            let frame: &mut Frame = &mut self.frames[level];
+            frame.split = true;
+            count -= taken;
+
+            for column in frame.rest.drain(..taken).rev() {
+                pieces.push(Piece {
+                    path: path.clone(),
+                    color: frame.color,
+                    column,
+                });
+            }
         }
 
-        return;
+        return pieces;
     }
 }
+
+// The moves searched for `color`, in the order of the move ordering, narrowed down by the
+// game's tactics as far as the backup rule allows it.
+fn candidate_moves<G: Game>(
+    game: &G,
+    color: TokenColor,
+    curr_depth: usize,
+    cpu_color: TokenColor,
+    rule: Backup,
+    ordering: &MoveOrdering,
+) -> Vec<usize> {
+    let moves: Vec<usize> = ordering.order(game, color, curr_depth);
+
+    if !rule.takes_wins(color == cpu_color) {
+        return moves;
+    }
+
+    return game.tactical_moves(color, moves, rule.prunes_tactics());
+}
+
+// Value of a node without children, which `color` moved into. Called with that move on the
+// board, so a finished game ended at this ply. Past the horizon a result the game can already
+// tell, like a win on the next move, is still counted as proven, quiet positions are judged by
+// the game's evaluation.
+pub fn leaf_value<G: Game>(
+    game: &G,
+    color: TokenColor,
+    status: GameStatus,
+    cpu_color: TokenColor,
+) -> f64 {
+    let to_move: TokenColor = color.invert();
+
+    let (winner, end) = match status {
+        GameStatus::Finished(winner) => (winner, game.ply()),
+        // A drawn game, like a full board, ends before any result it seemed headed for
+        GameStatus::InProgress if game.legal_moves(to_move).is_empty() => return NEUTRAL_VALUE,
+        GameStatus::InProgress => match game.forced_result(to_move) {
+            Some(result) => result,
+            None => return game.evaluate(cpu_color, to_move),
+        },
+    };
+
+    return match winner == cpu_color {
+        true => win_value(end),
+        false => loss_value(end),
+    };
+}
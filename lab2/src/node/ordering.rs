@@ -0,0 +1,213 @@
+use super::table::{Entry, ValueTable};
+use crate::board::token::TokenColor;
+use crate::game::game::Game;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const MAX_TABLE_ENTRIES: usize = 1 << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct OrderingOptions {
+    pub center: bool,
+    pub killers: bool,
+    pub history: bool,
+    pub table: bool,
+}
+
+impl OrderingOptions {
+    pub fn all() -> OrderingOptions {
+        return Self {
+            center: true,
+            killers: true,
+            history: true,
+            table: true,
+        };
+    }
+
+    pub fn parse(input: &str) -> Option<OrderingOptions> {
+        let mut options: OrderingOptions = OrderingOptions::default();
+
+        for name in input.split(',') {
+            match name {
+                "none" => (),
+                "all" => options = OrderingOptions::all(),
+                "center" => options.center = true,
+                "killer" => options.killers = true,
+                "history" => options.history = true,
+                "table" => options.table = true,
+                _ => return None,
+            }
+        }
+
+        return Some(options);
+    }
+}
+
+// Decides in which order the columns of a position are searched, so that moves which are
+// likely to cause a cutoff come first. Without any options columns are tried left to right.
+// With a value table the ordering also looks up the values of positions searched before, by
+// other threads or on other ranks. In a Lazy SMP search every thread has its own ordering with
+// a variation that breaks ties differently in each thread.
+pub struct MoveOrdering {
+    options: OrderingOptions,
+    killers: Vec<[Option<usize>; 2]>,
+    history: HashMap<(TokenColor, usize), u64>,
+    table: HashMap<u64, (u64, usize)>,
+    values: Option<Box<dyn ValueTable>>,
+    variation: u64,
+}
+
+impl MoveOrdering {
+    pub fn new(options: OrderingOptions) -> MoveOrdering {
+        return Self {
+            options,
+            killers: vec![],
+            history: HashMap::new(),
+            table: HashMap::new(),
+            values: None,
+            variation: 0,
+        };
+    }
+
+    pub fn with_table(
+        options: OrderingOptions,
+        values: Box<dyn ValueTable>,
+        variation: u64,
+    ) -> MoveOrdering {
+        return Self {
+            values: Some(values),
+            variation,
+            ..Self::new(options)
+        };
+    }
+
+    pub fn order<G: Game>(self: &Self, game: &G, color: TokenColor, depth: usize) -> Vec<usize> {
+        let table_move: Option<usize> = self.table_move(game);
+        let killers: [Option<usize>; 2] = match self.options.killers {
+            true => self.killers.get(depth).copied().unwrap_or([None, None]),
+            false => [None, None],
+        };
+
+        let mut columns: Vec<usize> = game.legal_moves(color);
+
+        columns.sort_by_key(|&column| {
+            let history: u64 = match self.options.history {
+                true => self.history.get(&(color, column)).copied().unwrap_or(0),
+                false => 0,
+            };
+            let center: usize = match self.options.center {
+                true => column.abs_diff(game.mirror(column)),
+                false => 0,
+            };
+
+            return (
+                table_move != Some(column),
+                !killers.contains(&Some(column)),
+                u64::MAX - history,
+                center,
+                tiebreak(column, self.variation),
+            );
+        });
+
+        return columns;
+    }
+
+    // `remaining` is the number of plies still searched below the node that was cut off.
+    pub fn record_cutoff(
+        self: &mut Self,
+        color: TokenColor,
+        depth: usize,
+        remaining: usize,
+        column: usize,
+    ) {
+        if self.options.killers {
+            if self.killers.len() <= depth {
+                self.killers.resize(depth + 1, [None, None]);
+            }
+
+            let killers: &mut [Option<usize>; 2] = &mut self.killers[depth];
+
+            if killers[0] != Some(column) {
+                killers[1] = killers[0];
+                killers[0] = Some(column);
+            }
+        }
+
+        if self.options.history {
+            *self.history.entry((color, column)).or_insert(0) += (remaining * remaining) as u64;
+        }
+    }
+
+    // Remembers the best column of a position, keyed by its canonical hash so the entry is
+    // shared with the mirrored position.
+    pub fn store<G: Game>(self: &mut Self, game: &G, column: usize) {
+        if !self.options.table {
+            return;
+        }
+
+        if self.table.len() >= MAX_TABLE_ENTRIES {
+            self.table.clear();
+        }
+
+        self.table
+            .insert(game.canonical_hash(), (game.hash(), column));
+    }
+
+    // Value of the position for the cpu, searched at least `depth` plies deep.
+    pub fn probe<G: Game>(
+        self: &mut Self,
+        game: &G,
+        cpu_color: TokenColor,
+        depth: usize,
+    ) -> Option<f64> {
+        return self
+            .values
+            .as_mut()?
+            .probe(game.hash(), game.ply(), cpu_color, depth);
+    }
+
+    pub fn store_value<G: Game>(
+        self: &mut Self,
+        game: &G,
+        cpu_color: TokenColor,
+        depth: usize,
+        value: f64,
+    ) {
+        if let Some(values) = &mut self.values {
+            values.store(Entry {
+                hash: game.hash(),
+                ply: game.ply(),
+                color: cpu_color,
+                depth,
+                value,
+            });
+        }
+    }
+
+    pub fn is_stopped(self: &Self) -> bool {
+        return self.values.as_ref().is_some_and(|x| x.is_stopped());
+    }
+
+    fn table_move<G: Game>(self: &Self, game: &G) -> Option<usize> {
+        if !self.options.table {
+            return None;
+        }
+
+        let &(hash, column) = self.table.get(&game.canonical_hash())?;
+
+        if hash == game.hash() {
+            return Some(column);
+        }
+
+        return Some(game.mirror(column));
+    }
+}
+
+// Columns left to right without a variation, otherwise in an order scrambled by it.
+fn tiebreak(column: usize, variation: u64) -> u64 {
+    if variation == 0 {
+        return column as u64;
+    }
+
+    return ((column as u64) ^ variation).wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 32;
+}
@@ -0,0 +1,77 @@
+// Values are seen from the cpu's side. Positions the search could not decide stay within
+// [LOSE_VALUE, WIN_VALUE], proven results lie outside of it and are scored by the ply of the
+// winning move, counted from the empty board, so a faster win scores higher and a slower loss
+// scores less negative. Counting from the empty board keeps the values of subtrees searched
+// on different ranks comparable.
+pub const WIN_VALUE: f64 = 1.0;
+pub const LOSE_VALUE: f64 = -1.0;
+pub const NEUTRAL_VALUE: f64 = 0.0;
+const WIN_BASE: f64 = 1000.0;
+
+pub fn win_value(ply: usize) -> f64 {
+    return WIN_BASE - ply as f64;
+}
+
+pub fn loss_value(ply: usize) -> f64 {
+    return -win_value(ply);
+}
+
+pub fn is_win(value: f64) -> bool {
+    return value > WIN_VALUE;
+}
+
+pub fn is_loss(value: f64) -> bool {
+    return value < LOSE_VALUE;
+}
+
+// Ply of the winning move of a proven result.
+pub fn decided_at(value: f64) -> Option<usize> {
+    if !is_win(value) && !is_loss(value) {
+        return None;
+    }
+
+    return Some((WIN_BASE - value.abs()).round() as usize);
+}
+
+// Proven results count as plain wins and losses when values are averaged.
+pub fn clamp(value: f64) -> f64 {
+    return value.clamp(LOSE_VALUE, WIN_VALUE);
+}
+
+// "win in 2" when the side to move at `ply` wins with its second move from here, "loss in 1"
+// when the opponent wins with its next one.
+pub fn format_score(value: f64, ply: usize) -> String {
+    let Some(end) = decided_at(value) else {
+        return format!("{:+.3}", value);
+    };
+
+    let moves: usize = (end - ply + 1) / 2;
+
+    return match is_win(value) {
+        true => format!("win in {}", moves),
+        false => format!("loss in {}", moves),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn results_are_ordered_by_distance() {
+        assert!(win_value(7) > win_value(9));
+        assert!(loss_value(9) > loss_value(7));
+        assert!(win_value(42) > WIN_VALUE && loss_value(42) < LOSE_VALUE);
+        assert_eq!(decided_at(loss_value(13)), Some(13));
+        assert_eq!(decided_at(0.75), None);
+    }
+
+    #[test]
+    fn scores_count_moves_of_the_winner() {
+        assert_eq!(format_score(win_value(7), 6), "win in 1");
+        assert_eq!(format_score(win_value(9), 6), "win in 2");
+        assert_eq!(format_score(loss_value(8), 6), "loss in 1");
+        assert_eq!(format_score(loss_value(12), 6), "loss in 3");
+        assert_eq!(format_score(-0.25, 6), "-0.250");
+    }
+}
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+use std::{ops::AddAssign, time::Duration};
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct SearchStats {
+    pub nodes: u64,
+    pub cutoffs: u64,
+    // Positions whose value was taken from a value table instead of being searched.
+    pub table_hits: u64,
+    pub max_depth: usize,
+    pub elapsed: Duration,
+    pub table: TableStats,
+}
+
+// Traffic of the transposition table distributed across the workers.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct TableStats {
+    // Lookups of positions owned by another rank.
+    pub remote_probes: u64,
+    pub remote_hits: u64,
+    // Probes, replies and batches of stored entries sent, and their size.
+    pub messages: u64,
+    pub bytes: u64,
+    // Time spent waiting for the replies to remote probes.
+    pub wait: Duration,
+}
+
+impl TableStats {
+    pub fn hit_rate(self: &Self) -> f64 {
+        return self.remote_hits as f64 / self.remote_probes.max(1) as f64;
+    }
+}
+
+impl AddAssign for TableStats {
+    fn add_assign(&mut self, other: Self) {
+        self.remote_probes += other.remote_probes;
+        self.remote_hits += other.remote_hits;
+        self.messages += other.messages;
+        self.bytes += other.bytes;
+        self.wait += other.wait;
+    }
+}
+
+impl AddAssign for SearchStats {
+    fn add_assign(&mut self, other: Self) {
+        self.nodes += other.nodes;
+        self.cutoffs += other.cutoffs;
+        self.table_hits += other.table_hits;
+        self.max_depth = self.max_depth.max(other.max_depth);
+        self.elapsed += other.elapsed;
+        self.table += other.table;
+    }
+}
@@ -0,0 +1,336 @@
+use crate::board::token::TokenColor;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+const SHARDS: usize = 64;
+const MAX_SHARD_ENTRIES: usize = 1 << 16;
+// Entries of shallow searches are cheaper to recompute than to send to another rank.
+pub const MIN_SENT_DEPTH: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    pub hash: u64,
+    pub ply: usize,
+    // The cpu the value is counted for.
+    pub color: TokenColor,
+    // Plies searched below the position.
+    pub depth: usize,
+    pub value: f64,
+}
+
+impl Entry {
+    pub fn answers(self: &Self, hash: u64, ply: usize, color: TokenColor, depth: usize) -> bool {
+        return self.hash == hash && self.ply == ply && self.color == color && self.depth >= depth;
+    }
+}
+
+// Entries by position and cpu. A deeper entry of the same position is kept, a full map starts
+// over.
+pub struct EntryMap {
+    entries: HashMap<(u64, TokenColor), Entry>,
+    capacity: usize,
+}
+
+impl EntryMap {
+    pub fn new(capacity: usize) -> EntryMap {
+        return Self {
+            entries: HashMap::new(),
+            capacity,
+        };
+    }
+
+    pub fn probe(
+        self: &Self,
+        hash: u64,
+        ply: usize,
+        color: TokenColor,
+        depth: usize,
+    ) -> Option<f64> {
+        let entry: &Entry = self.entries.get(&(hash, color))?;
+
+        if !entry.answers(hash, ply, color, depth) {
+            return None;
+        }
+
+        return Some(entry.value);
+    }
+
+    // Returns whether the entry was taken.
+    pub fn insert(self: &mut Self, entry: Entry) -> bool {
+        if let Some(old) = self.entries.get(&(entry.hash, entry.color)) {
+            if old.ply == entry.ply && old.depth > entry.depth {
+                return false;
+            }
+        }
+
+        if self.entries.len() >= self.capacity {
+            self.entries.clear();
+        }
+
+        self.entries.insert((entry.hash, entry.color), entry);
+
+        return true;
+    }
+}
+
+// Where the search looks up the values of positions searched before, under the backup rule of
+// the search, and stores the ones it finds.
+pub trait ValueTable {
+    // Value of the position for `color` if it was searched at least `depth` plies deep.
+    fn probe(
+        self: &mut Self,
+        hash: u64,
+        ply: usize,
+        color: TokenColor,
+        depth: usize,
+    ) -> Option<f64>;
+
+    fn store(self: &mut Self, entry: Entry);
+
+    // Set once the result of the search is no longer needed.
+    fn is_stopped(self: &Self) -> bool {
+        return false;
+    }
+}
+
+// Values of the positions searched by all threads of a rank during one Lazy SMP search, which
+// share one backup rule. Locks are split by hash so threads rarely wait for each other.
+pub struct SharedTable {
+    shards: Vec<Mutex<EntryMap>>,
+    // Entries stored here since they were last sent to the other ranks.
+    fresh: Mutex<Vec<Entry>>,
+    stopped: AtomicBool,
+}
+
+impl SharedTable {
+    pub fn new() -> SharedTable {
+        return Self {
+            shards: (0..SHARDS)
+                .map(|_| Mutex::new(EntryMap::new(MAX_SHARD_ENTRIES)))
+                .collect(),
+            fresh: Mutex::new(vec![]),
+            stopped: AtomicBool::new(false),
+        };
+    }
+
+    pub fn probe(
+        self: &Self,
+        hash: u64,
+        ply: usize,
+        color: TokenColor,
+        depth: usize,
+    ) -> Option<f64> {
+        return self.shards[hash as usize % SHARDS]
+            .lock()
+            .unwrap()
+            .probe(hash, ply, color, depth);
+    }
+
+    // Searches that were stopped leave incomplete values behind, they are not stored.
+    pub fn store(self: &Self, entry: Entry) {
+        if self.is_stopped() {
+            return;
+        }
+
+        if self.insert(entry) && entry.depth >= MIN_SENT_DEPTH {
+            self.fresh.lock().unwrap().push(entry);
+        }
+    }
+
+    // Adds entries received from other ranks, they are not sent on again.
+    pub fn merge(self: &Self, entries: &[Entry]) {
+        for &entry in entries {
+            self.insert(entry);
+        }
+    }
+
+    pub fn take_fresh(self: &Self) -> Vec<Entry> {
+        return std::mem::take(&mut *self.fresh.lock().unwrap());
+    }
+
+    pub fn stop(self: &Self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_stopped(self: &Self) -> bool {
+        return self.stopped.load(Ordering::Relaxed);
+    }
+
+    fn insert(self: &Self, entry: Entry) -> bool {
+        return self.shards[entry.hash as usize % SHARDS]
+            .lock()
+            .unwrap()
+            .insert(entry);
+    }
+}
+
+impl ValueTable for Arc<SharedTable> {
+    fn probe(
+        self: &mut Self,
+        hash: u64,
+        ply: usize,
+        color: TokenColor,
+        depth: usize,
+    ) -> Option<f64> {
+        return SharedTable::probe(self, hash, ply, color, depth);
+    }
+
+    fn store(self: &mut Self, entry: Entry) {
+        SharedTable::store(self, entry);
+    }
+
+    fn is_stopped(self: &Self) -> bool {
+        return SharedTable::is_stopped(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{
+        board::{Board, GameStatus},
+        notation::parse_moves,
+        token::TokenColor,
+    };
+    use crate::game::game::Game;
+    use crate::node::{
+        backup::Backup,
+        node::Node,
+        ordering::{MoveOrdering, OrderingOptions},
+        score::win_value,
+        stats::SearchStats,
+    };
+    use std::thread;
+
+    const CPU: TokenColor = TokenColor::Yellow;
+
+    fn search(board: &Board, depth: usize, ordering: &mut MoveOrdering) -> (Node, SearchStats) {
+        let mut root: Node = Node::new(CPU.invert(), 0, GameStatus::InProgress);
+        let mut stats: SearchStats = SearchStats::default();
+        root.search(
+            &mut board.clone(),
+            depth,
+            0,
+            CPU,
+            Backup::Minimax,
+            ordering,
+            &mut stats,
+        );
+
+        return (root, stats);
+    }
+
+    fn entry(board: &Board, depth: usize, value: f64) -> Entry {
+        return Entry {
+            hash: board.hash(),
+            ply: board.ply(),
+            color: CPU,
+            depth,
+            value,
+        };
+    }
+
+    #[test]
+    fn deeper_entries_are_kept() {
+        let table: SharedTable = SharedTable::new();
+        let mut board: Board = Board::default();
+        board.play_line(&[3, 3], TokenColor::Yellow).unwrap();
+
+        table.store(entry(&board, 4, 0.5));
+        table.store(entry(&board, 1, -0.5));
+
+        assert_eq!(table.probe(board.hash(), 2, CPU, 4), Some(0.5));
+        assert_eq!(table.probe(board.hash(), 2, CPU, 5), None);
+        assert_eq!(table.probe(board.hash(), 2, CPU.invert(), 0), None);
+        assert_eq!(table.take_fresh().len(), 1);
+        assert!(table.take_fresh().is_empty());
+
+        board.apply(2, TokenColor::Yellow).unwrap();
+        assert_eq!(table.probe(board.hash(), 3, CPU, 0), None);
+    }
+
+    #[test]
+    fn full_map_starts_over() {
+        let mut map: EntryMap = EntryMap::new(2);
+        let mut board: Board = Board::default();
+
+        for column in 0..3 {
+            board.apply(column, TokenColor::Yellow).unwrap();
+            assert!(map.insert(entry(&board, 1, 0.0)));
+        }
+
+        assert_eq!(map.probe(board.hash(), 3, CPU, 1), Some(0.0));
+        board.undo(2).unwrap();
+        assert_eq!(map.probe(board.hash(), 2, CPU, 1), None);
+    }
+
+    #[test]
+    fn stopped_searches_are_not_stored() {
+        let table: SharedTable = SharedTable::new();
+        let board: Board = Board::default();
+
+        table.stop();
+        table.store(entry(&board, 3, 0.25));
+        assert_eq!(table.probe(board.hash(), 0, CPU, 0), None);
+
+        table.merge(&[entry(&board, 3, 0.25)]);
+        assert_eq!(table.probe(board.hash(), 0, CPU, 3), Some(0.25));
+        assert!(table.take_fresh().is_empty());
+    }
+
+    #[test]
+    fn threads_sharing_a_table_find_the_win() {
+        let mut board: Board = Board::default();
+        board
+            .play(&parse_moves("010102").unwrap(), TokenColor::Yellow)
+            .unwrap();
+
+        let table: Arc<SharedTable> = Arc::new(SharedTable::new());
+
+        let (root, _) = thread::scope(|scope| {
+            let helpers: Vec<_> = (1..4)
+                .map(|id| {
+                    let board: Board = board.clone();
+                    let table: Arc<SharedTable> = table.clone();
+
+                    scope.spawn(move || {
+                        let mut ordering: MoveOrdering =
+                            MoveOrdering::with_table(OrderingOptions::all(), Box::new(table), id);
+
+                        return search(&board, 3 + id as usize % 2, &mut ordering);
+                    })
+                })
+                .collect();
+
+            let mut ordering: MoveOrdering =
+                MoveOrdering::with_table(OrderingOptions::all(), Box::new(table.clone()), 0);
+            let result: (Node, SearchStats) = search(&board, 3, &mut ordering);
+
+            for handle in helpers {
+                handle.join().unwrap();
+            }
+
+            return result;
+        });
+
+        assert_eq!(root.best_child(CPU).unwrap().column, 0);
+        assert_eq!(root.value, Some(win_value(7)));
+
+        // A second search of a quiet position takes the values of the first.
+        let quiet: Board = Board::default();
+        let table: Arc<SharedTable> = Arc::new(SharedTable::new());
+        let mut ordering: MoveOrdering =
+            MoveOrdering::with_table(OrderingOptions::all(), Box::new(table), 0);
+        let (first, _) = search(&quiet, 3, &mut ordering);
+        let (second, stats) = search(&quiet, 3, &mut ordering);
+
+        assert_eq!(second.value, first.value);
+        assert!(stats.table_hits > 0);
+    }
+}
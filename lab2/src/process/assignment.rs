@@ -1,19 +1,51 @@
-use crate::{board::board::Board, node::node::Node};
+use crate::{
+    board::token::TokenColor,
+    game::game::Game,
+    node::arena::{NodeId, Tree},
+    node::backup::Backup,
+    node::stats::SearchStats,
+};
 use serde::{Deserialize, Serialize};
 
+// Game already requires positions and moves to be serializable.
 #[derive(Serialize, Deserialize, Debug)]
-pub struct Assignment {
-    pub node: Node,
-    pub board: Board,
-    pub indexes: (usize, usize),
+#[serde(bound = "")]
+pub struct Assignment<G: Game> {
+    // The node of the task as the root, the searched subtree below it in the result. It travels
+    // in the layout of the master's tree, which grafts it as it is.
+    pub tree: Tree,
+    // The master's root position and the moves from there to the node, `color` moving first.
+    // Two moves for the tasks the master creates, more for pieces of a split task. The worker
+    // plays them before it searches.
+    pub board: G,
+    pub line: Vec<G::Move>,
+    pub color: TokenColor,
+    pub depth: usize,
+    pub backup: Backup,
+    // Node of the master's tree the result is grafted onto.
+    pub target: NodeId,
+    pub stats: SearchStats,
 }
 
-impl Assignment {
-    pub fn new(node: Node, board: Board, indexes: (usize, usize)) -> Assignment {
+impl<G: Game> Assignment<G> {
+    pub fn new(
+        tree: Tree,
+        board: G,
+        line: Vec<G::Move>,
+        color: TokenColor,
+        depth: usize,
+        backup: Backup,
+        target: NodeId,
+    ) -> Assignment<G> {
         return Self {
-            node,
+            tree,
             board,
-            indexes,
+            line,
+            color,
+            depth,
+            backup,
+            target,
+            stats: SearchStats::default(),
         };
     }
 }
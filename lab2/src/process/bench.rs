@@ -0,0 +1,140 @@
+use serde::Serialize;
+use std::{fs, io};
+
+// Opening, early middlegame and middlegame positions, as move sequences from the empty board.
+pub const BENCH_POSITIONS: [&str; 8] = [
+    "-",
+    "3",
+    "33",
+    "3342",
+    "233445",
+    "332244",
+    "33332222",
+    "3424334422",
+];
+
+// Solved positions with their exact score for the side to move: positive when it wins, negative
+// when it loses and 0 for a draw. A win with the k-th last own stone of the board scores k, so
+// the score of a win on the next move is (cells - moves played + 1) / 2, as in Pascal Pons'
+// solver. The first four are the known theory results of the opening.
+pub const SOLVED_POSITIONS: [(&str, i32); 28] = [
+    ("-", 1),
+    ("3", -1),
+    ("2", 0),
+    ("4", 0),
+    ("43454432021323", -2),
+    ("145656300302601", 0),
+    ("56142402006113", -2),
+    ("64100535432445", -5),
+    ("4550334601235136", 3),
+    ("3633022335305625", 0),
+    ("51640256666133551", 12),
+    ("223153050423404261", -1),
+    ("336244204115125205", 0),
+    ("0203330143615006051", 7),
+    ("6245053462341120004", 10),
+    ("321024053403600", 11),
+    ("34450544236566413246", 0),
+    ("11611420131253660335", -10),
+    ("616221635544525346240", 1),
+    ("4510414634411251033363", 9),
+    ("62206024364004326145040", -9),
+    ("33022601315611555541656", -6),
+    ("632465423661300556600512", 6),
+    ("4325461440526415402163153", 5),
+    ("60260454663431136036125001", 0),
+    ("23532222603113002550616556", 7),
+    ("434531141120403556315514054", -7),
+    ("3306631016034104001645156414363", 4),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    // Plies up to and including the winning move.
+    Win(usize),
+    Loss(usize),
+    Unknown,
+}
+
+// Number of plies from the position up to and including the winning move, None for a draw.
+pub fn plies_to_end(score: i32, moves: usize, cells: usize) -> Option<usize> {
+    if score == 0 {
+        return None;
+    }
+
+    let winner: usize = if score > 0 {
+        moves % 2
+    } else {
+        (moves + 1) % 2
+    };
+    let mut last: usize = cells + 1 - 2 * score.unsigned_abs() as usize;
+
+    if last % 2 != winner {
+        last -= 1;
+    }
+
+    return Some(last + 1 - moves);
+}
+
+// What a search that sees `horizon` plies ahead has to prove, down to how soon the game ends,
+// wins and losses further away can only be guessed.
+pub fn expected_outcome(score: i32, moves: usize, cells: usize, horizon: usize) -> Outcome {
+    return match plies_to_end(score, moves, cells) {
+        Some(plies) if plies <= horizon && score > 0 => Outcome::Win(plies),
+        Some(plies) if plies <= horizon => Outcome::Loss(plies),
+        _ => Outcome::Unknown,
+    };
+}
+
+// A search agrees with the solved score when it proves the expected outcome, or proves nothing
+// if none is expected, as long as it does not claim the opposite result.
+pub fn agrees(score: i32, expected: Outcome, outcome: Outcome) -> bool {
+    return match (expected, outcome) {
+        (Outcome::Unknown, Outcome::Win(_)) => score > 0,
+        (Outcome::Unknown, Outcome::Loss(_)) => score < 0,
+        _ => expected == outcome,
+    };
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchResult {
+    pub position: String,
+    pub ranks: i32,
+    pub workers: i32,
+    pub depth: usize,
+    pub best_move: usize,
+    pub nodes: u64,
+    pub elapsed: f64,
+    pub nodes_per_second: f64,
+}
+
+pub const CSV_HEADER: &str =
+    "position,ranks,workers,depth,best_move,nodes,elapsed,nodes_per_second";
+
+impl BenchResult {
+    pub fn to_csv(self: &Self) -> String {
+        return format!(
+            "{},{},{},{},{},{},{},{}",
+            self.position,
+            self.ranks,
+            self.workers,
+            self.depth,
+            self.best_move,
+            self.nodes,
+            self.elapsed,
+            self.nodes_per_second
+        );
+    }
+}
+
+// The format is picked by the file extension, anything but `.json` is written as CSV.
+pub fn save_results(path: &str, results: &[BenchResult]) -> io::Result<()> {
+    if path.ends_with(".json") {
+        return fs::write(path, serde_json::to_string_pretty(results).unwrap());
+    }
+
+    let mut lines: Vec<String> = vec![CSV_HEADER.to_string()];
+    lines.extend(results.iter().map(|x| x.to_csv()));
+
+    return fs::write(path, lines.join("\n") + "\n");
+}
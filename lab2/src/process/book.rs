@@ -0,0 +1,126 @@
+use crate::board::{
+    board::Board,
+    notation::{format_moves, parse_moves},
+    token::TokenColor,
+};
+use crate::game::game::Game;
+use std::{collections::HashMap, fs, io};
+
+#[derive(Debug, Clone)]
+pub struct BookEntry {
+    pub moves: Vec<usize>,
+    pub color: TokenColor,
+    pub best_move: usize,
+    pub score: f64,
+}
+
+// Positions are stored by the move sequence that reaches them, one per line:
+// `<moves> <color to move> <best move> <score>`, and looked up by canonical board hash
+// together with the hash of the stored orientation, so mirrored positions share an entry.
+pub struct Book {
+    entries: HashMap<u64, (u64, BookEntry)>,
+}
+
+impl Book {
+    pub fn new() -> Book {
+        return Self {
+            entries: HashMap::new(),
+        };
+    }
+
+    pub fn load(path: &str, board: &Board) -> Result<Book, String> {
+        let content: String = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let mut book: Book = Book::new();
+
+        for (i, line) in content.lines().enumerate() {
+            let line: &str = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = || format!("{}:{}: invalid book entry", path, i + 1);
+
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let [moves, color, best_move, score] = parts[..] else {
+                return Err(invalid());
+            };
+
+            let entry: BookEntry = BookEntry {
+                moves: parse_moves(moves).ok_or_else(invalid)?,
+                color: match color {
+                    "Red" => TokenColor::Red,
+                    "Yellow" => TokenColor::Yellow,
+                    _ => return Err(invalid()),
+                },
+                best_move: best_move.parse().map_err(|_| invalid())?,
+                score: score.parse().map_err(|_| invalid())?,
+            };
+
+            let first: TokenColor = if entry.moves.len() % 2 == 0 {
+                entry.color
+            } else {
+                entry.color.invert()
+            };
+
+            let mut position: Board = board.clone();
+            position.play(&entry.moves, first).map_err(|_| invalid())?;
+
+            if !position.is_move_legal(entry.best_move) {
+                return Err(invalid());
+            }
+
+            book.insert(&position, entry);
+        }
+
+        return Ok(book);
+    }
+
+    pub fn save(self: &Self, path: &str) -> io::Result<()> {
+        let mut entries: Vec<&BookEntry> = self.entries.values().map(|x| &x.1).collect();
+        entries.sort_by(|a, b| (a.moves.len(), &a.moves).cmp(&(b.moves.len(), &b.moves)));
+
+        let mut content: String = "# <moves> <color to move> <best move> <score>\n".to_string();
+
+        for entry in entries {
+            content += &format!(
+                "{} {:?} {} {}\n",
+                format_moves(&entry.moves),
+                entry.color,
+                entry.best_move,
+                entry.score
+            );
+        }
+
+        return fs::write(path, content);
+    }
+
+    pub fn insert(self: &mut Self, board: &Board, entry: BookEntry) {
+        self.entries
+            .insert(board.canonical_hash(), (board.hash(), entry));
+    }
+
+    // Returns the best move and score for `board`, mirrored if the entry was stored the other
+    // way around.
+    pub fn get(self: &Self, board: &Board, color: TokenColor) -> Option<(usize, f64)> {
+        let (hash, entry) = self.entries.get(&board.canonical_hash())?;
+
+        if entry.color != color {
+            return None;
+        }
+
+        if *hash == board.hash() {
+            return Some((entry.best_move, entry.score));
+        }
+
+        return Some((Game::mirror(board, entry.best_move), entry.score));
+    }
+
+    pub fn contains(self: &Self, board: &Board) -> bool {
+        return self.entries.contains_key(&board.canonical_hash());
+    }
+
+    pub fn len(self: &Self) -> usize {
+        return self.entries.len();
+    }
+}
@@ -0,0 +1,119 @@
+use crate::board::token::TokenColor;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone)]
+pub struct Clock {
+    red: Duration,
+    yellow: Duration,
+    increment: Duration,
+    started: Option<Instant>,
+    // Time of the current move before the clock was paused.
+    used: Duration,
+}
+
+impl Clock {
+    pub fn new(base: Duration, increment: Duration) -> Clock {
+        return Self {
+            red: base,
+            yellow: base,
+            increment,
+            started: None,
+            used: Duration::ZERO,
+        };
+    }
+
+    pub fn remaining(self: &Self, color: TokenColor) -> Duration {
+        return match color {
+            TokenColor::Red => self.red,
+            TokenColor::Yellow => self.yellow,
+        };
+    }
+
+    pub fn start(self: &mut Self) {
+        self.started = Some(Instant::now());
+        self.used = Duration::ZERO;
+    }
+
+    // Stops counting the current move until `resume`, like while the mover asks for a hint.
+    pub fn pause(self: &mut Self) {
+        self.used = self.elapsed();
+        self.started = None;
+    }
+
+    pub fn resume(self: &mut Self) {
+        self.started.get_or_insert_with(Instant::now);
+    }
+
+    // Time spent on the current move so far, without the pauses.
+    pub fn elapsed(self: &Self) -> Duration {
+        return match self.started {
+            Some(started) => self.used + started.elapsed(),
+            None => self.used,
+        };
+    }
+
+    // Time `color` has left while they are on the move.
+    pub fn left(self: &Self, color: TokenColor) -> Duration {
+        return self.remaining(color).saturating_sub(self.elapsed());
+    }
+
+    // Charges the time since `start` to `color`, returns false if their flag fell.
+    pub fn stop(self: &mut Self, color: TokenColor) -> bool {
+        let elapsed: Duration = self.elapsed();
+        self.started = None;
+        self.used = Duration::ZERO;
+
+        let remaining: &mut Duration = match color {
+            TokenColor::Red => &mut self.red,
+            TokenColor::Yellow => &mut self.yellow,
+        };
+
+        if elapsed >= *remaining {
+            *remaining = Duration::ZERO;
+            return false;
+        }
+
+        *remaining = *remaining - elapsed + self.increment;
+
+        return true;
+    }
+
+    // Time the engine may spend on its next move, spread over the moves it can still make.
+    pub fn budget(self: &Self, color: TokenColor, moves_left: usize) -> Duration {
+        let remaining: Duration = self.remaining(color);
+        let share: Duration = remaining / (moves_left.max(1) as u32) + self.increment;
+
+        return share.min(remaining / 2);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn paused_time_is_not_charged() {
+        let mut clock: Clock = Clock::new(Duration::from_millis(100), Duration::ZERO);
+
+        clock.start();
+        clock.pause();
+        thread::sleep(Duration::from_millis(150));
+        clock.resume();
+
+        assert!(clock.left(TokenColor::Red) > Duration::ZERO);
+        assert!(clock.stop(TokenColor::Red));
+    }
+
+    #[test]
+    fn flag_falls_while_on_the_move() {
+        let mut clock: Clock = Clock::new(Duration::from_millis(20), Duration::ZERO);
+
+        clock.start();
+        thread::sleep(Duration::from_millis(40));
+
+        assert_eq!(clock.left(TokenColor::Yellow), Duration::ZERO);
+        assert!(!clock.stop(TokenColor::Yellow));
+        assert_eq!(clock.remaining(TokenColor::Red), Duration::from_millis(20));
+    }
+}
@@ -0,0 +1,38 @@
+use crate::board::board::Move;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Move(Move),
+    Undo,
+    Hint,
+    Show,
+    Save(String),
+    Resign,
+    Quit,
+}
+
+impl Command {
+    pub fn parse(input: &str) -> Option<Command> {
+        let mut words = input.split_whitespace();
+        let command: &str = words.next()?;
+
+        let parsed: Command = match command {
+            "undo" => Command::Undo,
+            "hint" => Command::Hint,
+            "show" => Command::Show,
+            "save" => Command::Save(words.next()?.to_string()),
+            "resign" => Command::Resign,
+            "quit" => Command::Quit,
+            _ => match command.strip_prefix('p') {
+                Some(column) => Command::Move(Move::Pop(column.parse().ok()?)),
+                None => Command::Move(Move::Drop(command.parse().ok()?)),
+            },
+        };
+
+        if words.next().is_some() {
+            return None;
+        }
+
+        return Some(parsed);
+    }
+}
@@ -0,0 +1,316 @@
+use super::{error::ProcessError, tag::Tag};
+use crate::{
+    board::token::TokenColor,
+    node::{
+        stats::TableStats,
+        table::{Entry, EntryMap, ValueTable, MIN_SENT_DEPTH},
+    },
+};
+use mpi::{topology::*, traits::*};
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+    thread,
+    time::{Duration, Instant},
+};
+
+const MAX_ENTRIES: usize = 1 << 22;
+// Entries for another rank are collected and sent together.
+const STORE_BATCH: usize = 64;
+// Messages from other ranks are handled every this many lookups and stores of the own search.
+const SERVE_INTERVAL: u64 = 64;
+// Pause before looking again when a rank waiting for a reply finds only messages it cannot
+// handle yet, like a request of the master to split its task.
+const WAIT_INTERVAL: Duration = Duration::from_micros(200);
+
+type Probe = (u64, usize, TokenColor, usize);
+
+// Entries for the rank that owns them. The last batch of a task is confirmed, batches from
+// one rank arrive in order, so then the owner has all of them.
+#[derive(Serialize, Deserialize)]
+struct Batch {
+    entries: Vec<Entry>,
+    confirm: bool,
+}
+
+// A transposition table split across the workers by hash, every worker keeps the entries of
+// the positions it owns. Lookups of positions owned by another rank are sent to it and the
+// search waits for the reply, stores are sent in batches without waiting. A worker answers the
+// others whenever it uses the table itself and while it waits for work, so a probe is only
+// held up by the search between two table accesses of the owner. Values are kept per cpu and
+// the backup rule does not change during a run, so entries stay valid from search to search.
+//
+// The search cannot take errors from the table, the first one is kept and stops the search
+// instead, the worker picks it up afterwards.
+pub struct DistributedTable {
+    world: SystemCommunicator,
+    owners: Vec<i32>,
+    entries: EntryMap,
+    pending: HashMap<i32, Vec<Entry>>,
+    // Ranks sent entries since the last confirmed flush.
+    unconfirmed: HashSet<i32>,
+    accesses: u64,
+    stats: TableStats,
+    failure: Option<ProcessError>,
+}
+
+impl DistributedTable {
+    pub fn new(world: SystemCommunicator, master_rank: i32) -> DistributedTable {
+        return Self {
+            world,
+            owners: (0..world.size()).filter(|&x| x != master_rank).collect(),
+            entries: EntryMap::new(MAX_ENTRIES),
+            pending: HashMap::new(),
+            unconfirmed: HashSet::new(),
+            accesses: 0,
+            stats: TableStats::default(),
+            failure: None,
+        };
+    }
+
+    // Answers the probes and takes in the entries other ranks sent since the last call.
+    pub fn serve(self: &mut Self) -> Result<(), ProcessError> {
+        while let Some(status) = self
+            .world
+            .any_process()
+            .immediate_probe_with_tag(Tag::TableProbe as i32)
+        {
+            let rank: i32 = status.source_rank();
+            let (msg, _) = self
+                .world
+                .process_at_rank(rank)
+                .receive_vec_with_tag::<u8>(Tag::TableProbe as i32);
+
+            let (hash, ply, color, depth): Probe = bincode::deserialize(&msg)?;
+            let value: Option<f64> = self.entries.probe(hash, ply, color, depth);
+
+            self.send(rank, &value, Tag::TableReply)?;
+        }
+
+        while let Some(status) = self
+            .world
+            .any_process()
+            .immediate_probe_with_tag(Tag::TableStore as i32)
+        {
+            let rank: i32 = status.source_rank();
+            let (msg, _) = self
+                .world
+                .process_at_rank(rank)
+                .receive_vec_with_tag::<u8>(Tag::TableStore as i32);
+
+            let batch: Batch = bincode::deserialize(&msg)?;
+
+            for entry in batch.entries {
+                self.entries.insert(entry);
+            }
+
+            if batch.confirm {
+                self.send(rank, &(), Tag::TableStored)?;
+            }
+        }
+
+        return Ok(());
+    }
+
+    // Sends the entries still collected for other ranks and waits until every rank sent
+    // entries since the last flush has them, so none are left in flight after a task.
+    pub fn flush(self: &mut Self) -> Result<(), ProcessError> {
+        let mut ranks: HashSet<i32> = std::mem::take(&mut self.unconfirmed);
+        ranks.extend(self.pending.keys());
+
+        for &rank in ranks.iter() {
+            let entries: Vec<Entry> = self.pending.remove(&rank).unwrap_or_default();
+            self.send(
+                rank,
+                &Batch {
+                    entries,
+                    confirm: true,
+                },
+                Tag::TableStore,
+            )?;
+        }
+
+        for _ in ranks.iter() {
+            self.wait_for(Tag::TableStored)?;
+        }
+
+        return Ok(());
+    }
+
+    // The error that stopped the last search, if there was one.
+    pub fn take_failure(self: &mut Self) -> Option<ProcessError> {
+        return self.failure.take();
+    }
+
+    pub fn take_stats(self: &mut Self) -> TableStats {
+        return std::mem::take(&mut self.stats);
+    }
+
+    fn owner(self: &Self, hash: u64) -> i32 {
+        return self.owners[(hash % self.owners.len() as u64) as usize];
+    }
+
+    fn flush_rank(self: &mut Self, rank: i32) -> Result<(), ProcessError> {
+        let Some(entries) = self.pending.remove(&rank) else {
+            return Ok(());
+        };
+
+        self.unconfirmed.insert(rank);
+
+        return self.send(
+            rank,
+            &Batch {
+                entries,
+                confirm: false,
+            },
+            Tag::TableStore,
+        );
+    }
+
+    fn send<T: Serialize>(
+        self: &mut Self,
+        rank: i32,
+        value: &T,
+        tag: Tag,
+    ) -> Result<(), ProcessError> {
+        let msg: Vec<u8> = bincode::serialize(value)?;
+
+        self.stats.messages += 1;
+        self.stats.bytes += msg.len() as u64;
+
+        self.world
+            .process_at_rank(rank)
+            .send_with_tag(&msg, tag as i32);
+
+        return Ok(());
+    }
+
+    // Waits for a message with the tag, from whichever rank sends it, and answers the other
+    // ranks meanwhile. The owner of a position may itself be waiting for this rank. Blocks
+    // until a message comes in, and only polls while messages for later are queued.
+    fn wait_for(self: &mut Self, tag: Tag) -> Result<Vec<u8>, ProcessError> {
+        let tag: i32 = tag as i32;
+
+        loop {
+            if let Some(status) = self.world.any_process().immediate_probe_with_tag(tag) {
+                let (msg, _) = self
+                    .world
+                    .process_at_rank(status.source_rank())
+                    .receive_vec_with_tag::<u8>(tag);
+
+                return Ok(msg);
+            }
+
+            self.serve()?;
+
+            let next: i32 = self.world.any_process().probe().tag();
+
+            if next != tag && next != Tag::TableProbe as i32 && next != Tag::TableStore as i32 {
+                thread::sleep(WAIT_INTERVAL);
+            }
+        }
+    }
+
+    fn remote_probe(
+        self: &mut Self,
+        owner: i32,
+        probe: Probe,
+    ) -> Result<Option<f64>, ProcessError> {
+        let start = Instant::now();
+        self.stats.remote_probes += 1;
+        self.send(owner, &probe, Tag::TableProbe)?;
+
+        let value: Option<f64> = bincode::deserialize(&self.wait_for(Tag::TableReply)?)?;
+
+        self.stats.wait += start.elapsed();
+
+        if value.is_some() {
+            self.stats.remote_hits += 1;
+        }
+
+        return Ok(value);
+    }
+
+    fn tick(self: &mut Self) {
+        self.accesses += 1;
+
+        if self.accesses % SERVE_INTERVAL == 0 {
+            let result: Result<(), ProcessError> = self.serve();
+            self.fail(result);
+        }
+    }
+
+    fn fail<T>(self: &mut Self, result: Result<T, ProcessError>) -> Option<T> {
+        return match result {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.failure.get_or_insert(e);
+                None
+            }
+        };
+    }
+}
+
+impl ValueTable for Rc<RefCell<DistributedTable>> {
+    fn probe(
+        self: &mut Self,
+        hash: u64,
+        ply: usize,
+        color: TokenColor,
+        depth: usize,
+    ) -> Option<f64> {
+        let mut table = self.borrow_mut();
+        table.tick();
+
+        let owner: i32 = table.owner(hash);
+
+        if owner == table.world.rank() {
+            return table.entries.probe(hash, ply, color, depth);
+        }
+
+        if depth < MIN_SENT_DEPTH {
+            return None;
+        }
+
+        let result: Result<Option<f64>, ProcessError> =
+            table.remote_probe(owner, (hash, ply, color, depth));
+
+        return table.fail(result).flatten();
+    }
+
+    // Like SharedTable::store, a search stopped by a failure leaves incomplete values behind.
+    fn store(self: &mut Self, entry: Entry) {
+        let mut table = self.borrow_mut();
+
+        if table.failure.is_some() {
+            return;
+        }
+
+        table.tick();
+
+        let owner: i32 = table.owner(entry.hash);
+
+        if owner == table.world.rank() {
+            table.entries.insert(entry);
+            return;
+        }
+
+        if entry.depth < MIN_SENT_DEPTH {
+            return;
+        }
+
+        let pending: &mut Vec<Entry> = table.pending.entry(owner).or_default();
+        pending.push(entry);
+
+        if pending.len() >= STORE_BATCH {
+            let result: Result<(), ProcessError> = table.flush_rank(owner);
+            table.fail(result);
+        }
+    }
+
+    fn is_stopped(self: &Self) -> bool {
+        return self.borrow().failure.is_some();
+    }
+}
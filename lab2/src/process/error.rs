@@ -0,0 +1,46 @@
+use crate::board::board::MoveError;
+use std::{error, fmt};
+
+#[derive(Debug)]
+pub enum ProcessError {
+    Move(MoveError),
+    // A message that does not fit the request/response exchange between master and workers.
+    Protocol(String),
+    Serialization(bincode::Error),
+    // MPI aborts the job on its own errors, this covers what is detected above it, like a run
+    // without any worker ranks.
+    Transport(String),
+}
+
+impl fmt::Display for ProcessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            ProcessError::Move(e) => write!(f, "{}", e),
+            ProcessError::Protocol(message) => write!(f, "protocol error: {}", message),
+            ProcessError::Serialization(e) => write!(f, "serialization error: {}", e),
+            ProcessError::Transport(message) => write!(f, "transport failure: {}", message),
+        }
+    }
+}
+
+impl error::Error for ProcessError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        return match self {
+            ProcessError::Move(e) => Some(e),
+            ProcessError::Serialization(e) => Some(e.as_ref()),
+            ProcessError::Protocol(_) | ProcessError::Transport(_) => None,
+        };
+    }
+}
+
+impl From<MoveError> for ProcessError {
+    fn from(e: MoveError) -> Self {
+        return ProcessError::Move(e);
+    }
+}
+
+impl From<bincode::Error> for ProcessError {
+    fn from(e: bincode::Error) -> Self {
+        return ProcessError::Serialization(e);
+    }
+}
@@ -0,0 +1,78 @@
+use crate::{
+    board::{board::GameStatus, token::TokenColor},
+    game::game::Game,
+    node::{
+        backup::Backup,
+        node::Node,
+        ordering::{MoveOrdering, OrderingOptions},
+        stats::SearchStats,
+        table::SharedTable,
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::{sync::Arc, time::Duration, time::Instant};
+
+// How often a worker sends the entries its threads found and receives those of the others.
+pub const EXCHANGE_INTERVAL: Duration = Duration::from_millis(20);
+
+// The root position of a Lazy SMP search, which every rank searches as a whole.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(bound = "")]
+pub struct LazyTask<G: Game> {
+    pub board: G,
+    pub color: TokenColor,
+    pub last_column: usize,
+    // Horizon of the master's search, counted from the root.
+    pub depth: usize,
+    pub backup: Backup,
+    pub threads: usize,
+    // Number of the rank's first helper thread, so that helpers on different ranks vary.
+    pub first_helper: usize,
+}
+
+impl<G: Game> LazyTask<G> {
+    pub fn root(self: &Self) -> Node {
+        return Node::new(
+            self.color.invert(),
+            self.last_column,
+            GameStatus::InProgress,
+        );
+    }
+}
+
+// A helper deepens its search one ply at a time, so the shallow iterations fill the table
+// early, up to the master's horizon or one ply past it for every other helper. The moves are
+// ordered a little differently in each helper so they spread over the tree instead of all
+// searching the same moves first. Runs until the table is stopped or the last iteration is done.
+pub fn helper<G: Game>(
+    task: LazyTask<G>,
+    id: usize,
+    table: Arc<SharedTable>,
+    options: OrderingOptions,
+) -> SearchStats {
+    let start = Instant::now();
+    let mut game: G = task.board.clone();
+    let mut ordering: MoveOrdering =
+        MoveOrdering::with_table(options, Box::new(table.clone()), id as u64);
+    let mut stats: SearchStats = SearchStats::default();
+
+    for depth in 0..=task.depth + id % 2 {
+        if table.is_stopped() {
+            break;
+        }
+
+        task.root().search(
+            &mut game,
+            depth,
+            0,
+            task.color,
+            task.backup,
+            &mut ordering,
+            &mut stats,
+        );
+    }
+
+    stats.elapsed = start.elapsed();
+
+    return stats;
+}
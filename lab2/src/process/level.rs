@@ -0,0 +1,127 @@
+use rand::{distributions::WeightedIndex, prelude::Distribution, Rng};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Level {
+    pub depth: usize,
+    // Softmax temperature over the root scores, 0 always plays the best move.
+    pub temperature: f64,
+}
+
+pub const LEVELS: [Level; 5] = [
+    Level {
+        depth: 0,
+        temperature: 0.5,
+    },
+    Level {
+        depth: 1,
+        temperature: 0.25,
+    },
+    Level {
+        depth: 2,
+        temperature: 0.1,
+    },
+    Level {
+        depth: 3,
+        temperature: 0.03,
+    },
+    Level {
+        depth: 4,
+        temperature: 0.0,
+    },
+];
+
+impl Level {
+    pub fn get(number: usize) -> Option<Level> {
+        return LEVELS.get(number.checked_sub(1)?).copied();
+    }
+
+    // None without any moves to choose from.
+    pub fn choose_move(self: &Self, scores: &[(usize, f64)], rng: &mut impl Rng) -> Option<usize> {
+        let mut best: (usize, f64) = *scores.first()?;
+
+        for &(column, value) in scores.iter() {
+            if value > best.1 {
+                best = (column, value);
+            }
+        }
+
+        if self.temperature <= 0.0 {
+            return Some(best.0);
+        }
+
+        let weights: Vec<f64> = scores
+            .iter()
+            .map(|(_, value)| ((value - best.1) / self.temperature).exp())
+            .collect();
+
+        let Ok(distribution) = WeightedIndex::new(&weights) else {
+            return Some(best.0);
+        };
+
+        return Some(scores[distribution.sample(rng)].0);
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MatchScore {
+    pub wins: usize,
+    pub losses: usize,
+    pub draws: usize,
+}
+
+impl MatchScore {
+    pub fn games(self: &Self) -> usize {
+        return self.wins + self.losses + self.draws;
+    }
+
+    // A match without games counts as even.
+    pub fn score(self: &Self) -> f64 {
+        if self.games() == 0 {
+            return 0.5;
+        }
+
+        return (self.wins as f64 + 0.5 * self.draws as f64) / self.games() as f64;
+    }
+
+    // Standard score of the result against an even match, above 2 is a clear difference.
+    pub fn z(self: &Self) -> f64 {
+        if self.games() == 0 {
+            return 0.0;
+        }
+
+        return (self.score() - 0.5) / (0.5 / (self.games() as f64).sqrt());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn no_moves_to_choose_from() {
+        let mut rng: StdRng = StdRng::seed_from_u64(0);
+
+        for level in LEVELS {
+            assert_eq!(level.choose_move(&[], &mut rng), None);
+            assert_eq!(level.choose_move(&[(3, 0.2)], &mut rng), Some(3));
+        }
+    }
+
+    #[test]
+    fn match_without_games_is_even() {
+        let score: MatchScore = MatchScore::default();
+
+        assert_eq!(score.score(), 0.5);
+        assert_eq!(score.z(), 0.0);
+
+        let score: MatchScore = MatchScore {
+            wins: 3,
+            losses: 1,
+            draws: 0,
+        };
+
+        assert_eq!(score.score(), 0.75);
+        assert_eq!(score.z(), 1.0);
+    }
+}
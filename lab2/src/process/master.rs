@@ -1,217 +1,1487 @@
-use super::{assignment::Assignment, tag::Tag};
+use super::{
+    assignment::Assignment,
+    bench::{
+        agrees, expected_outcome, save_results, BenchResult, Outcome, BENCH_POSITIONS,
+        SOLVED_POSITIONS,
+    },
+    book::{Book, BookEntry},
+    clock::Clock,
+    command::Command,
+    error::ProcessError,
+    lazy::{helper, LazyTask},
+    level::{Level, MatchScore, LEVELS},
+    player::Player,
+    record::GameRecord,
+    report::{LoadBalance, SearchReport, WorkerReport},
+    tag::Tag,
+};
 use crate::{
-    board::board::{Board, GameStatus},
+    board::board::{Board, GameStatus, Move},
+    board::notation::{format_line, parse_moves},
     board::token::TokenColor,
-    node::node::Node,
+    config::config::{Config, Parallel},
+    game::{game::Game, tictactoe::TicTacToe},
+    node::{
+        arena::{NodeId, Tree, ROOT},
+        backup::Backup,
+        export::ExportNode,
+        node::Node,
+        ordering::{MoveOrdering, OrderingOptions},
+        score::{decided_at, format_score, is_win},
+        stats::SearchStats,
+        table::{Entry, SharedTable},
+    },
+    ui::ui::Ui,
 };
 
-use mpi::{topology::*, traits::*};
+use mpi::{point_to_point::Status, topology::*, traits::*};
+use rand::{rngs::StdRng, SeedableRng};
 use std::{
-    io::{self, Write},
-    time::Instant,
+    collections::{BTreeMap, HashMap},
+    fs::{File, OpenOptions},
+    io::Write,
+    sync::Arc,
+    thread::{self, ScopedJoinHandle},
+    time::{Duration, Instant},
 };
 
+const FIRST_COLOR: TokenColor = TokenColor::Yellow;
+// How long the master sleeps when no worker has sent table entries.
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+// A task whose position is the same as, or the mirror image of, an already created one.
+struct Duplicate {
+    target: NodeId,
+    source: NodeId,
+    mirrored: bool,
+}
+
 pub struct Master {
     world: SystemCommunicator,
     board: Board,
-    cpu_color: TokenColor,
-    player_color: TokenColor,
+    first: Player,
+    second: Player,
+    step: bool,
+    depth: usize,
+    levels: (Level, Level),
+    rng: StdRng,
+    clock: Option<Clock>,
+    // The clock at the start of every ply, for undo.
+    clocks: Vec<Clock>,
+    book: Option<Book>,
+    ordering: MoveOrdering,
+    // Ordering of the master's own search threads in a Lazy SMP search.
+    options: OrderingOptions,
+    backup: Backup,
+    parallel: Parallel,
+    threads: usize,
+    steal: bool,
+    stats: SearchStats,
+    balance: LoadBalance,
+    workers: BTreeMap<i32, WorkerReport>,
+    // Workers whose request for work arrived after all tasks were handed out.
+    idle: Vec<i32>,
+    stats_file: Option<File>,
+    record_path: Option<String>,
+    // Every search writes its tree here, a {ply} in the name is replaced by the ply.
+    export_path: Option<String>,
+    export_depth: usize,
+    export_principal: bool,
+    history: Vec<usize>,
+    ui: Box<dyn Ui>,
 }
 
 impl Master {
-    pub fn new(world: SystemCommunicator, board: Board) -> Self {
+    pub fn new(world: SystemCommunicator, board: Board, ui: Box<dyn Ui>, config: &Config) -> Self {
         return Self {
             world,
             board,
-            cpu_color: TokenColor::Red,
-            player_color: TokenColor::Yellow,
+            first: config.first,
+            second: config.second,
+            step: config.step,
+            depth: config.depth,
+            levels: match config.levels {
+                Some((first, second)) => (Level::get(first).unwrap(), Level::get(second).unwrap()),
+                None => {
+                    let level: Level = Level {
+                        depth: config.depth,
+                        temperature: 0.0,
+                    };
+
+                    (level, level)
+                }
+            },
+            rng: match config.seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_entropy(),
+            },
+            clock: config.time.map(|x| Clock::new(x, config.increment)),
+            clocks: vec![],
+            book: None,
+            ordering: MoveOrdering::new(OrderingOptions {
+                center: config.ordering.center,
+                ..OrderingOptions::default()
+            }),
+            options: config.ordering,
+            backup: config.backup,
+            parallel: config.parallel,
+            threads: config.threads,
+            steal: config.steal,
+            stats: SearchStats::default(),
+            balance: LoadBalance::default(),
+            workers: BTreeMap::new(),
+            idle: vec![],
+            stats_file: None,
+            record_path: config.record.clone(),
+            export_path: config.export_tree.clone(),
+            export_depth: config.export_depth,
+            export_principal: config.export_principal,
+            history: vec![],
+            ui,
         };
     }
 
-    pub fn run(self: &mut Self) {
+    pub fn load_book(self: &mut Self, path: &str) -> Result<(), String> {
+        let book: Book = Book::load(path, &self.board)?;
+
+        self.ui.message(&format!(
+            "Loaded {} book positions from {}",
+            book.len(),
+            path
+        ));
+        self.book = Some(book);
+
+        return Ok(());
+    }
+
+    // Every engine search appends one JSON line to the file.
+    pub fn open_stats(self: &mut Self, path: &str) -> Result<(), String> {
+        let file: File = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Could not open {}: {}", path, e))?;
+
+        self.stats_file = Some(file);
+
+        return Ok(());
+    }
+
+    pub fn run(self: &mut Self) -> Result<(), ProcessError> {
+        let result: Result<(), ProcessError> = self.play();
+
+        if let Some(path) = self.record_path.clone() {
+            self.save(&path);
+        }
+
+        return result;
+    }
+
+    fn play(self: &mut Self) -> Result<(), ProcessError> {
         loop {
-            let input_column = self.player_input();
+            let color: TokenColor = self.color_at(self.history.len());
+
+            if self.step && self.first == Player::Engine && self.second == Player::Engine {
+                let text: String = format!("{:#?} to move, press Enter to continue", color);
 
-            let status = match self.board.get_status() {
-                GameStatus::Finished(color) => {
-                    println!("The winner is {:#?}!", color);
+                if !self.ui.pause(&self.board, &text) {
                     break;
                 }
-                _ => GameStatus::InProgress,
+            }
+
+            if let Some(clock) = &mut self.clock {
+                self.clocks.truncate(self.history.len());
+                self.clocks.push(clock.clone());
+                clock.start();
+            }
+
+            let played: Option<usize> = match self.player(color) {
+                Player::Human => self.player_input(color)?,
+                Player::Engine => self.engine_move(color)?,
             };
 
-            let start = Instant::now();
+            if played.is_none() {
+                break;
+            }
 
-            let mut root: Node = Node::new(self.player_color, input_column, status);
-            root.build_tree(&mut self.board, 1, 0);
+            if let Some(clock) = &mut self.clock {
+                if !clock.stop(color) {
+                    self.out_of_time(color);
+                    break;
+                }
 
-            let mut requests: Vec<Assignment> = vec![];
-            let mut responses: Vec<Assignment> = vec![];
+                self.ui.show_clock(clock);
+            }
 
-            self.create_requests(&root, &mut requests);
+            if let Some((color, lines)) = self.board.get_winning_lines() {
+                self.ui.game_over(&format!(
+                    "The winner is {:#?}! Four in a row at {}",
+                    color,
+                    lines
+                        .iter()
+                        .map(|x| format_line(x))
+                        .collect::<Vec<String>>()
+                        .join(" and ")
+                ));
+                break;
+            }
 
-            self.send_requests(&mut requests, &mut responses);
+            if self.board.is_draw(&self.history) {
+                self.ui.game_over("The game is a draw!");
+                break;
+            }
+        }
 
-            let best_move = self.calculate_best_move(&mut root, responses);
+        return Ok(());
+    }
 
-            self.board.make_move(best_move, self.cpu_color).unwrap();
+    fn out_of_time(&mut self, color: TokenColor) {
+        self.ui.game_over(&format!(
+            "{:#?} ran out of time, the winner is {:#?}!",
+            color,
+            color.invert()
+        ));
+    }
 
-            println!(
-                "{}",
-                root.children
-                    .iter()
-                    .map(|x| x.value.unwrap().to_string())
-                    .collect::<Vec<String>>()
-                    .join(", ")
+    fn color_at(self: &Self, ply: usize) -> TokenColor {
+        if ply % 2 == 0 {
+            return FIRST_COLOR;
+        }
+
+        return FIRST_COLOR.invert();
+    }
+
+    fn player(self: &Self, color: TokenColor) -> Player {
+        if color == FIRST_COLOR {
+            return self.first;
+        }
+
+        return self.second;
+    }
+
+    fn level(self: &Self, color: TokenColor) -> Level {
+        if color == FIRST_COLOR {
+            return self.levels.0;
+        }
+
+        return self.levels.1;
+    }
+
+    fn engine_move(&mut self, color: TokenColor) -> Result<Option<usize>, ProcessError> {
+        if let Some((best_move, score)) = self.book_move(color) {
+            let score: String = format_score(score, self.board.ply());
+
+            self.board.apply(best_move, color)?;
+            self.history.push(best_move);
+
+            self.ui.message(&format!(
+                "Book move: {} (score {})",
+                self.board.decode(best_move),
+                score
+            ));
+            self.ui.show_move(&self.board, &self.history);
+
+            return Ok(Some(best_move));
+        }
+
+        let budget: Option<Duration> = self.clock.as_ref().map(|clock| {
+            // Pops let a PopOut game go on past a full board
+            let moves_left: usize =
+                ((self.board.rows * self.board.columns).saturating_sub(self.history.len()) + 1) / 2;
+
+            clock.budget(color, moves_left)
+        });
+
+        let mut report: SearchReport = self.think(color, self.level(color), budget)?;
+        let best_move: usize = report.best_move;
+
+        self.board.apply(best_move, color)?;
+        self.history.push(best_move);
+
+        if let Some((_, lines)) = self.board.get_winning_lines() {
+            report.winning_lines = lines;
+        }
+
+        if let Some(file) = &mut self.stats_file {
+            if let Err(e) = writeln!(file, "{}", report.to_json()) {
+                self.ui
+                    .message(&format!("Could not write statistics: {}", e));
+            }
+        }
+
+        self.ui.show_report(&report);
+        self.ui.show_move(&self.board, &self.history);
+
+        return Ok(Some(best_move));
+    }
+
+    // The book is only consulted by levels that always play their best move.
+    fn book_move(self: &Self, color: TokenColor) -> Option<(usize, f64)> {
+        if self.level(color).temperature > 0.0 {
+            return None;
+        }
+
+        return self.book.as_ref()?.get(&self.board, color);
+    }
+
+    fn think(
+        &mut self,
+        color: TokenColor,
+        level: Level,
+        budget: Option<Duration>,
+    ) -> Result<SearchReport, ProcessError> {
+        let start = Instant::now();
+        let last_column: usize = self.history.last().copied().unwrap_or(0);
+
+        self.stats = SearchStats::default();
+        self.balance = LoadBalance::default();
+        self.workers.clear();
+
+        let mut board: Board = self.board.clone();
+        let (tree, _) = match budget {
+            Some(budget) => {
+                self.timed_search(&mut board, color, last_column, budget, level.depth)?
+            }
+            None => self.search(&mut board, color, last_column, level.depth)?,
+        };
+
+        let scores: Vec<(usize, f64)> = tree
+            .children(ROOT)
+            .iter()
+            .map(|&x| tree.node(x).unwrap())
+            .map(|x| (x.column, x.value.unwrap()))
+            .collect();
+
+        let best_move: usize = level
+            .choose_move(&scores, &mut self.rng)
+            .ok_or_else(|| ProcessError::Protocol("the search returned no moves".to_string()))?;
+        let elapsed: f64 = start.elapsed().as_secs_f64();
+
+        if let Some(path) = &self.export_path {
+            let path: String = path.replace("{ply}", &self.history.len().to_string());
+            let export: ExportNode = ExportNode::new(
+                &tree,
+                color,
+                self.board.ply(),
+                self.export_depth,
+                self.export_principal,
             );
-            println!("Best move: {}", best_move);
-            println!("Elapsed time: {:#?}", start.elapsed());
 
-            self.board.show();
+            match export.save(&path) {
+                Ok(_) => self.ui.message(&format!(
+                    "{} tree nodes written to {}",
+                    export.count(),
+                    path
+                )),
+                Err(e) => self.ui.message(&format!("Could not write tree: {}", e)),
+            }
+        }
+
+        return Ok(SearchReport {
+            ply: self.history.len(),
+            color,
+            ranks: self.world.size(),
+            scores,
+            best_move,
+            principal_variation: tree.principal_variation(color),
+            nodes: self.stats.nodes,
+            cutoffs: self.stats.cutoffs,
+            table_hits: self.stats.table_hits,
+            table: self.stats.table,
+            balance: self.balance,
+            // Workers of the split search count depth from the grandchildren of the root.
+            max_depth: match self.parallel {
+                Parallel::Split => self.stats.max_depth + 2,
+                Parallel::Lazy => self.stats.max_depth,
+            },
+            elapsed,
+            nodes_per_second: self.stats.nodes as f64 / elapsed.max(f64::EPSILON),
+            workers: self.workers.values().cloned().collect(),
+            tree_nodes: tree.len(),
+            tree_memory: tree.memory(),
+            winning_lines: vec![],
+        });
+    }
 
-            match self.board.get_status() {
-                GameStatus::Finished(color) => {
-                    println!("The winner is {:#?}!", color);
-                    break;
+    // The engine plays tic-tac-toe against itself through the same distributed search as
+    // Connect Four, searched to the end the game has to be a draw.
+    pub fn run_tictactoe(self: &mut Self) -> Result<(), ProcessError> {
+        let mut game: TicTacToe = TicTacToe::default();
+        let mut color: TokenColor = FIRST_COLOR;
+        let mut last_move: usize = 0;
+
+        while game.status() == GameStatus::InProgress && !game.legal_moves(color).is_empty() {
+            // Every empty cell is one ply left, the search ends early where the game does.
+            let depth: usize = game.legal_moves(color).len();
+            let (tree, best_move) = self.search(&mut game, color, last_move, depth)?;
+
+            let scores: Vec<String> = tree
+                .children(ROOT)
+                .iter()
+                .map(|&x| tree.node(x).unwrap())
+                .map(|x| {
+                    format!(
+                        "{} {}",
+                        x.column,
+                        format_score(x.value.unwrap(), game.ply())
+                    )
+                })
+                .collect();
+
+            game.apply(best_move, color)?;
+
+            self.ui.message(&format!(
+                "{:?} plays {} ({})\n{}",
+                color,
+                best_move,
+                scores.join(", "),
+                game
+            ));
+
+            last_move = best_move;
+            color = color.invert();
+        }
+
+        match game.status() {
+            GameStatus::Finished(color) => self.ui.message(&format!("{:?} wins", color)),
+            GameStatus::InProgress => self.ui.message("Draw"),
+        }
+
+        return Ok(());
+    }
+
+    // Plays every level against the next one up, alternating who moves first, and reports
+    // the stronger side's score.
+    pub fn run_gauntlet(self: &mut Self, games: usize) -> Result<(), ProcessError> {
+        for i in 1..LEVELS.len() {
+            let mut score: MatchScore = MatchScore::default();
+
+            for game in 0..games {
+                let (weaker, stronger) = (LEVELS[i - 1], LEVELS[i]);
+
+                let winner: Option<TokenColor> = if game % 2 == 0 {
+                    self.play_engine_game(stronger, weaker)?
+                } else {
+                    self.play_engine_game(weaker, stronger)?
+                };
+
+                let stronger_color: TokenColor = if game % 2 == 0 {
+                    FIRST_COLOR
+                } else {
+                    FIRST_COLOR.invert()
+                };
+
+                match winner {
+                    Some(color) if color == stronger_color => score.wins += 1,
+                    Some(_) => score.losses += 1,
+                    None => score.draws += 1,
                 }
-                _ => GameStatus::InProgress,
+            }
+
+            self.ui.message(&format!(
+                "Level {} vs level {}: +{} -{} ={}, score {:.3}, z {:.2}",
+                i + 1,
+                i,
+                score.wins,
+                score.losses,
+                score.draws,
+                score.score(),
+                score.z()
+            ));
+        }
+
+        return Ok(());
+    }
+
+    // Searches every position of the benchmark suite to a fixed depth, run with different
+    // numbers of ranks and compare the outputs with the speedup tool.
+    pub fn run_benchmark(self: &mut Self, output: Option<&str>) -> Result<(), ProcessError> {
+        let mut results: Vec<BenchResult> = vec![];
+        let level: Level = Level {
+            depth: self.depth,
+            temperature: 0.0,
+        };
+
+        for position in BENCH_POSITIONS {
+            self.set_position(&parse_moves(position).unwrap())?;
+
+            let color: TokenColor = self.color_at(self.history.len());
+            let report: SearchReport = self.think(color, level, None)?;
+
+            let result: BenchResult = BenchResult {
+                position: position.to_string(),
+                ranks: report.ranks,
+                workers: report.ranks - 1,
+                depth: self.depth,
+                best_move: report.best_move,
+                nodes: report.nodes,
+                elapsed: report.elapsed,
+                nodes_per_second: report.nodes_per_second,
             };
+
+            self.ui.message(&format!(
+                "{:>12}  best {}  {} nodes  {:.3}s  {:.0} nodes/s",
+                result.position,
+                result.best_move,
+                result.nodes,
+                result.elapsed,
+                result.nodes_per_second
+            ));
+
+            results.push(result);
         }
 
-        self.notify_game_finished();
+        let elapsed: f64 = results.iter().map(|x| x.elapsed).sum();
+        let nodes: u64 = results.iter().map(|x| x.nodes).sum();
+
+        self.ui.message(&format!(
+            "Total: {} nodes in {:.3}s with {} workers",
+            nodes,
+            elapsed,
+            self.world.size() - 1
+        ));
+
+        if let Some(path) = output {
+            match save_results(path, &results) {
+                Ok(_) => self.ui.message(&format!("Results written to {}", path)),
+                Err(e) => self.ui.message(&format!("Could not write results: {}", e)),
+            }
+        }
+
+        return self.reset();
     }
 
-    fn player_input(&mut self) -> usize {
-        let mut input_line: String;
-        let mut input_column: usize;
+    // Checks the engine against the solved positions: within its horizon it has to prove the
+    // known result and it may never claim the opposite one.
+    pub fn run_suite(self: &mut Self) -> Result<(), ProcessError> {
+        let level: Level = Level {
+            depth: self.depth,
+            temperature: 0.0,
+        };
+        let cells: usize = self.board.rows * self.board.columns;
+        // The root and its children are expanded by the master, the workers search `depth` plies
+        // below the grandchildren and see the moves into the leaves.
+        let horizon: usize = self.depth + 3;
 
-        loop {
-            print!("Enter column index: ");
-            io::stdout().flush().unwrap();
-            input_line = String::new();
+        let mut mismatches: usize = 0;
+        let mut nodes: u64 = 0;
+        let mut elapsed: f64 = 0.0;
+
+        for (position, score) in SOLVED_POSITIONS {
+            let moves: Vec<usize> = parse_moves(position).unwrap();
+            self.set_position(&moves)?;
+
+            let color: TokenColor = self.color_at(self.history.len());
+            let report: SearchReport = self.think(color, level, None)?;
+
+            let value: f64 = report.scores.iter().map(|x| x.1).fold(f64::MIN, f64::max);
+            let outcome: Outcome = match decided_at(value) {
+                Some(end) if is_win(value) => Outcome::Win(end - moves.len()),
+                Some(end) => Outcome::Loss(end - moves.len()),
+                None => Outcome::Unknown,
+            };
+
+            let expected: Outcome = expected_outcome(score, moves.len(), cells, horizon);
+            let agreed: bool = agrees(score, expected, outcome);
+
+            if !agreed {
+                mismatches += 1;
+            }
+
+            nodes += report.nodes;
+            elapsed += report.elapsed;
+
+            self.ui.message(&format!(
+                "{:>32} {:>+3}  expected {:?}, found {:?} ({})  {} nodes  {:.3}s{}",
+                position,
+                score,
+                expected,
+                outcome,
+                format_score(value, moves.len()),
+                report.nodes,
+                report.elapsed,
+                if agreed { "" } else { "  MISMATCH" }
+            ));
+        }
+
+        self.ui.message(&format!(
+            "{} of {} positions agree, {} mismatches, {} nodes in {:.3}s ({:.0} nodes/s)",
+            SOLVED_POSITIONS.len() - mismatches,
+            SOLVED_POSITIONS.len(),
+            mismatches,
+            nodes,
+            elapsed,
+            nodes as f64 / elapsed.max(f64::EPSILON)
+        ));
+
+        return self.reset();
+    }
+
+    fn set_position(&mut self, moves: &[usize]) -> Result<(), ProcessError> {
+        self.reset()?;
+
+        for &column in moves {
+            let color: TokenColor = self.color_at(self.history.len());
+
+            self.board.make_move(column, color)?;
+            self.history.push(column);
+        }
+
+        return Ok(());
+    }
+
+    // Searches every position reached in fewer than `ply` moves and stores the best moves.
+    pub fn generate_book(self: &mut Self, path: &str, ply: usize) -> Result<(), ProcessError> {
+        let mut book: Book = Book::new();
+
+        self.reset()?;
+        self.extend_book(&mut book, ply)?;
+
+        match book.save(path) {
+            Ok(_) => self
+                .ui
+                .message(&format!("Wrote {} book positions to {}", book.len(), path)),
+            Err(e) => self.ui.message(&format!("Could not write book: {}", e)),
+        }
 
-            let Ok(_) = io::stdin().read_line(&mut input_line) else {
-                println!("Invalid input");
+        return Ok(());
+    }
+
+    fn extend_book(&mut self, book: &mut Book, ply: usize) -> Result<(), ProcessError> {
+        if self.history.len() >= ply || book.contains(&self.board) {
+            return Ok(());
+        }
+
+        let color: TokenColor = self.color_at(self.history.len());
+        let level: Level = Level {
+            depth: self.depth,
+            temperature: 0.0,
+        };
+
+        let report: SearchReport = self.think(color, level, None)?;
+        let best_move: usize = report.best_move;
+        let score: f64 = report.scores.iter().find(|x| x.0 == best_move).unwrap().1;
+
+        book.insert(
+            &self.board,
+            BookEntry {
+                moves: self.history.clone(),
+                color,
+                best_move,
+                score,
+            },
+        );
+
+        for column in 0..self.board.columns {
+            if !self.board.is_move_legal(column) {
                 continue;
+            }
+
+            self.board.make_move(column, color)?;
+            self.history.push(column);
+
+            if self.board.get_status() == GameStatus::InProgress && !self.board.is_full() {
+                self.extend_book(book, ply)?;
+            }
+
+            self.history.pop();
+            self.board.undo_move(column)?;
+        }
+
+        return Ok(());
+    }
+
+    fn play_engine_game(
+        &mut self,
+        first: Level,
+        second: Level,
+    ) -> Result<Option<TokenColor>, ProcessError> {
+        self.reset()?;
+
+        loop {
+            let color: TokenColor = self.color_at(self.history.len());
+            let level: Level = if color == FIRST_COLOR { first } else { second };
+
+            let best_move: usize = self.think(color, level, None)?.best_move;
+
+            self.board.apply(best_move, color)?;
+            self.history.push(best_move);
+
+            if let GameStatus::Finished(color) = self.board.get_status() {
+                return Ok(Some(color));
+            }
+
+            if self.board.is_draw(&self.history) {
+                return Ok(None);
+            }
+        }
+    }
+
+    fn reset(&mut self) -> Result<(), ProcessError> {
+        while let Some(index) = self.history.pop() {
+            self.board.undo(index)?;
+        }
+
+        return Ok(());
+    }
+
+    fn player_input(&mut self, color: TokenColor) -> Result<Option<usize>, ProcessError> {
+        loop {
+            let limit: Option<Duration> = self.clock.as_ref().map(|x| x.left(color));
+
+            let Some(command) = self.ui.read_command(&self.board, color, limit) else {
+                // The flag falls while waiting for the move
+                let flagged: bool = self
+                    .clock
+                    .as_mut()
+                    .is_some_and(|x| x.left(color).is_zero() && !x.stop(color));
+
+                if flagged {
+                    self.out_of_time(color);
+                }
+
+                return Ok(None);
             };
 
-            input_column = match input_line.trim().parse() {
-                Ok(x) => x,
-                Err(_) => {
-                    println!("Invalid input");
-                    continue;
+            match command {
+                Command::Move(mv) => {
+                    if let Err(e) = self.board.play_move(mv, color) {
+                        self.ui.message(&format!("Invalid input: {}", e));
+                        continue;
+                    }
+
+                    self.history.push(self.board.encode(mv));
+                    self.ui.show_move(&self.board, &self.history);
+
+                    return Ok(Some(self.board.encode(mv)));
+                }
+                Command::Undo => self.undo()?,
+                Command::Hint => self.hint(color)?,
+                Command::Show => self.ui.show_board(&self.board, &self.history),
+                Command::Save(path) => self.save(&path),
+                Command::Resign => {
+                    self.ui.game_over(&format!(
+                        "{:#?} resigns, the winner is {:#?}!",
+                        color,
+                        color.invert()
+                    ));
+                    return Ok(None);
                 }
+                Command::Quit => return Ok(None),
+            }
+        }
+    }
+
+    // Takes back moves up to and including the last one made by a human.
+    fn undo(&mut self) -> Result<(), ProcessError> {
+        let Some(ply) = (0..self.history.len())
+            .rev()
+            .find(|&x| self.player(self.color_at(x)) == Player::Human)
+        else {
+            self.ui.message("Nothing to undo");
+            return Ok(());
+        };
+
+        while self.history.len() > ply {
+            let index: usize = self.history.pop().unwrap();
+            self.board.undo(index)?;
+        }
+
+        // The clock goes back to the start of the ply taken back and runs from now
+        if let Some(mut clock) = self.clocks.get(ply).cloned() {
+            self.clocks.truncate(ply + 1);
+            clock.start();
+            self.ui.show_clock(&clock);
+            self.clock = Some(clock);
+        }
+
+        self.ui.show_board(&self.board, &self.history);
+
+        return Ok(());
+    }
+
+    fn hint(&mut self, color: TokenColor) -> Result<(), ProcessError> {
+        let last_column: usize = self.history.last().copied().unwrap_or(0);
+        let mut board: Board = self.board.clone();
+
+        // The hint is not thought up by the mover
+        if let Some(clock) = &mut self.clock {
+            clock.pause();
+        }
+
+        let result: Result<(Tree, usize), ProcessError> =
+            self.search(&mut board, color, last_column, self.depth);
+
+        if let Some(clock) = &mut self.clock {
+            clock.resume();
+        }
+
+        let (_, best_move) = result?;
+
+        self.ui
+            .message(&format!("Hint: {}", self.board.decode(best_move)));
+
+        return Ok(());
+    }
+
+    fn save(&mut self, path: &str) {
+        let moves: Vec<Move> = self.history.iter().map(|&x| self.board.decode(x)).collect();
+        let record: GameRecord = match self.board.get_winning_lines() {
+            Some((color, lines)) => GameRecord::new(FIRST_COLOR, moves, Some(color), lines),
+            None => GameRecord::new(FIRST_COLOR, moves, None, vec![]),
+        };
+
+        match record.save(path) {
+            Ok(_) => self.ui.message(&format!("Game saved to {}", path)),
+            Err(e) => self.ui.message(&format!("Could not save game: {}", e)),
+        }
+    }
+
+    // Deepens the worker search one ply at a time while the next iteration is expected to fit
+    // into the budget, the result of the deepest finished iteration is played.
+    fn timed_search<G: Game>(
+        &mut self,
+        game: &mut G,
+        color: TokenColor,
+        last_column: usize,
+        budget: Duration,
+        max_depth: usize,
+    ) -> Result<(Tree, usize), ProcessError> {
+        let start = Instant::now();
+
+        let mut result: (Tree, usize) = self.search(game, color, last_column, 0)?;
+        let mut iteration: Duration = start.elapsed();
+        let mut growth: f64 = game.legal_moves(color).len() as f64;
+        let mut depth: usize = 0;
+
+        while depth < max_depth && start.elapsed() + iteration.mul_f64(growth) <= budget {
+            let iteration_start = Instant::now();
+
+            depth += 1;
+            result = self.search(game, color, last_column, depth)?;
+
+            let elapsed: Duration = iteration_start.elapsed();
+
+            if !iteration.is_zero() {
+                growth = (elapsed.as_secs_f64() / iteration.as_secs_f64()).max(1.0);
+            }
+
+            iteration = elapsed;
+        }
+
+        self.ui.message(&format!(
+            "Searched to depth {} within a budget of {:#?}",
+            depth, budget
+        ));
+
+        return Ok(result);
+    }
+
+    fn search<G: Game>(
+        &mut self,
+        game: &mut G,
+        color: TokenColor,
+        last_column: usize,
+        depth: usize,
+    ) -> Result<(Tree, usize), ProcessError> {
+        if self.parallel == Parallel::Lazy {
+            return self.lazy_search(game, color, last_column, depth);
+        }
+
+        if self.world.size() < 2 {
+            return Err(ProcessError::Transport(
+                "no worker ranks, start at least two processes".to_string(),
+            ));
+        }
+
+        let mut root: Node = Node::new(color.invert(), last_column, GameStatus::InProgress);
+        root.build_tree(game, 1, 0, color, self.backup, &self.ordering);
+
+        let mut tree: Tree = Tree::from_node(&root);
+        let mut requests: Vec<Assignment<G>> = vec![];
+        let mut responses: Vec<Assignment<G>> = vec![];
+        let mut duplicates: Vec<Duplicate> = vec![];
+
+        self.create_requests(game, &tree, color, depth, &mut requests, &mut duplicates)?;
+
+        self.send_requests(game, &mut requests, &mut responses, &mut tree)?;
+
+        let best_move = self.calculate_best_move(game, &mut tree, color, responses, duplicates)?;
+
+        return Ok((tree, best_move));
+    }
+
+    // Every rank searches the whole tree to the horizon of the split search. The master's main
+    // thread gives the result, the helper threads here and on the workers only fill the table
+    // it looks values up in, so a single rank works as well.
+    fn lazy_search<G: Game>(
+        &mut self,
+        game: &mut G,
+        color: TokenColor,
+        last_column: usize,
+        depth: usize,
+    ) -> Result<(Tree, usize), ProcessError> {
+        let task: LazyTask<G> = LazyTask {
+            board: game.clone(),
+            color,
+            last_column,
+            depth: depth + 2,
+            backup: self.backup,
+            threads: self.threads,
+            first_helper: 0,
+        };
+
+        self.start_workers(&task)?;
+
+        let table: Arc<SharedTable> = Arc::new(SharedTable::new());
+        let options: OrderingOptions = self.options;
+
+        let (root, stats, result) = thread::scope(|scope| {
+            let main: ScopedJoinHandle<(Node, SearchStats)> = {
+                let task: LazyTask<G> = task.clone();
+                let table: Arc<SharedTable> = table.clone();
+
+                scope.spawn(move || {
+                    let start = Instant::now();
+                    let mut game: G = task.board.clone();
+                    let mut ordering: MoveOrdering =
+                        MoveOrdering::with_table(options, Box::new(table), 0);
+                    let mut stats: SearchStats = SearchStats::default();
+                    let mut root: Node = task.root();
+
+                    root.search(
+                        &mut game,
+                        task.depth,
+                        0,
+                        task.color,
+                        task.backup,
+                        &mut ordering,
+                        &mut stats,
+                    );
+                    stats.elapsed = start.elapsed();
+
+                    (root, stats)
+                })
+            };
+
+            let helpers: Vec<ScopedJoinHandle<SearchStats>> = (1..self.threads)
+                .map(|i| {
+                    let task: LazyTask<G> = task.clone();
+                    let table: Arc<SharedTable> = table.clone();
+
+                    scope.spawn(move || helper(task, i, table, options))
+                })
+                .collect();
+
+            let result: Result<(), ProcessError> = self.exchange_entries(&table, &main);
+            table.stop();
+
+            let (root, mut stats) = main.join().unwrap();
+
+            for handle in helpers {
+                stats += handle.join().unwrap();
+            }
+
+            (root, stats, result)
+        });
+
+        self.stats += stats;
+
+        // The workers are stopped after a failed exchange as well, the error comes first.
+        let stopped: Result<(), ProcessError> = self.stop_workers();
+        result?;
+        stopped?;
+
+        let tree: Tree = Tree::from_node(&root);
+        let best: NodeId = tree.best_child(ROOT, color).unwrap();
+        let best_move: usize = tree.node(best).unwrap().column;
+
+        return Ok((tree, best_move));
+    }
+
+    // Workers wait for work with a request, they get the root position in return.
+    fn start_workers<G: Game>(&mut self, task: &LazyTask<G>) -> Result<(), ProcessError> {
+        for rank in 0..self.world.size() {
+            if rank == self.world.rank() {
+                continue;
+            }
+
+            if !self.idle.contains(&rank) {
+                let (msg, status) = self.world.process_at_rank(rank).receive_vec::<u8>();
+
+                match Tag::from_i32(status.tag()) {
+                    Some(Tag::Request) => (),
+                    _ => return Err(rank_error(rank, &status, &msg)),
+                }
+            }
+
+            let task: LazyTask<G> = LazyTask {
+                first_helper: rank as usize * self.threads,
+                ..task.clone()
             };
 
-            let Ok(_) = self.board.make_move(input_column, self.player_color) else {
-                println!("Invalid input");
+            self.world
+                .process_at_rank(rank)
+                .send_with_tag(&bincode::serialize(&task)?, Tag::Lazy as i32);
+
+            self.idle.retain(|&x| x != rank);
+        }
+
+        return Ok(());
+    }
+
+    // Trades table entries with the workers until the main search is done. A worker sends the
+    // entries its threads found and gets those found anywhere else since its last exchange.
+    // The log keeps the entries not yet sent to every worker, `sent` counts from its start
+    // including the entries already dropped from it.
+    fn exchange_entries<T>(
+        &mut self,
+        table: &SharedTable,
+        main: &ScopedJoinHandle<T>,
+    ) -> Result<(), ProcessError> {
+        let mut log: Vec<(i32, Entry)> = vec![];
+        let mut dropped: usize = 0;
+        let mut sent: HashMap<i32, usize> = HashMap::new();
+        let workers: usize = self.world.size() as usize - 1;
+
+        while !main.is_finished() {
+            let own: i32 = self.world.rank();
+            let fresh: Vec<Entry> = table.take_fresh();
+
+            // Alone the master has no one to pass its entries on to
+            if workers > 0 {
+                log.extend(fresh.into_iter().map(|x| (own, x)));
+            }
+
+            let Some(status) = self.world.any_process().immediate_probe() else {
+                thread::sleep(POLL_INTERVAL);
                 continue;
             };
 
-            self.board.show();
+            let rank: i32 = status.source_rank();
+            let (msg, status) = self.world.process_at_rank(rank).receive_vec::<u8>();
+
+            match Tag::from_i32(status.tag()) {
+                Some(Tag::Entries) => {
+                    let entries: Vec<Entry> = bincode::deserialize(&msg)?;
+                    let start: usize = sent.get(&rank).copied().unwrap_or(0) - dropped;
+                    let reply: Vec<Entry> = log[start..]
+                        .iter()
+                        .filter(|x| x.0 != rank)
+                        .map(|x| x.1)
+                        .collect();
+
+                    self.world
+                        .process_at_rank(rank)
+                        .send_with_tag(&bincode::serialize(&reply)?, Tag::Entries as i32);
+
+                    table.merge(&entries);
+                    log.extend(entries.into_iter().map(|x| (rank, x)));
+                    sent.insert(rank, dropped + log.len());
+
+                    if sent.len() == workers {
+                        let oldest: usize = sent.values().copied().min().unwrap();
+                        log.drain(..oldest - dropped);
+                        dropped = oldest;
+                    }
+                }
+                _ => return Err(rank_error(rank, &status, &msg)),
+            }
+        }
+
+        return Ok(());
+    }
+
+    // Each worker is told to stop at its next exchange and answers with its statistics. A
+    // worker that failed reports its error and asks for work again, it is left idle. All of
+    // them are stopped before the first error is returned.
+    fn stop_workers(&mut self) -> Result<(), ProcessError> {
+        let mut failure: Option<ProcessError> = None;
+
+        for rank in 0..self.world.size() {
+            if rank == self.world.rank() {
+                continue;
+            }
+
+            loop {
+                let (msg, status) = self.world.process_at_rank(rank).receive_vec::<u8>();
 
-            return input_column;
+                match Tag::from_i32(status.tag()) {
+                    Some(Tag::Entries) => self
+                        .world
+                        .process_at_rank(rank)
+                        .send_with_tag::<u8>(&0, Tag::Stop as i32),
+                    Some(Tag::Result) => {
+                        let stats: SearchStats = match bincode::deserialize(&msg) {
+                            Ok(stats) => stats,
+                            Err(e) => {
+                                failure.get_or_insert(e.into());
+                                break;
+                            }
+                        };
+                        let worker: &mut WorkerReport =
+                            self.workers.entry(rank).or_insert(WorkerReport {
+                                rank,
+                                ..WorkerReport::default()
+                            });
+
+                        worker.tasks += 1;
+                        worker.nodes += stats.nodes;
+                        worker.busy += stats.elapsed.as_secs_f64();
+                        self.stats += stats;
+
+                        break;
+                    }
+                    Some(Tag::Request) => {
+                        self.idle.push(rank);
+                        break;
+                    }
+                    Some(Tag::Error) => {
+                        failure.get_or_insert(rank_error(rank, &status, &msg));
+                    }
+                    _ => {
+                        failure.get_or_insert(rank_error(rank, &status, &msg));
+                        break;
+                    }
+                }
+            }
         }
+
+        return match failure {
+            Some(e) => Err(e),
+            None => Ok(()),
+        };
     }
 
-    fn create_requests(&mut self, root: &Node, tasks: &mut Vec<Assignment>) {
-        for (i, child1) in root.children.iter().enumerate() {
-            for (j, child2) in child1.children.iter().enumerate() {
-                let mut board_clone: Board = self.board.clone();
+    fn create_requests<G: Game>(
+        &mut self,
+        game: &G,
+        tree: &Tree,
+        color: TokenColor,
+        depth: usize,
+        tasks: &mut Vec<Assignment<G>>,
+        duplicates: &mut Vec<Duplicate>,
+    ) -> Result<(), ProcessError> {
+        let mut searched: HashMap<u64, (NodeId, u64)> = HashMap::new();
 
-                board_clone
-                    .make_move(child1.column, self.cpu_color)
-                    .unwrap();
+        for &child in tree.children(ROOT) {
+            for &grandchild in tree.children(child) {
+                let mut position: G = game.clone();
+                position.play_line(&tree.path(grandchild), color)?;
 
-                board_clone
-                    .make_move(child2.column, self.player_color)
-                    .unwrap();
+                if let Some(&(source, hash)) = searched.get(&position.canonical_hash()) {
+                    duplicates.push(Duplicate {
+                        target: grandchild,
+                        source,
+                        mirrored: hash != position.hash(),
+                    });
+                    continue;
+                }
+
+                searched.insert(position.canonical_hash(), (grandchild, position.hash()));
 
-                let task: Assignment = Assignment::new(child2.clone(), board_clone, (i, j));
+                let task: Assignment<G> = Assignment::new(
+                    tree.subtree(grandchild),
+                    game.clone(),
+                    tree.path(grandchild)
+                        .iter()
+                        .map(|&x| game.decode(x))
+                        .collect(),
+                    color,
+                    depth,
+                    self.backup,
+                    grandchild,
+                );
 
                 tasks.push(task);
             }
         }
+
+        return Ok(());
     }
 
-    fn send_requests(&mut self, tasks: &mut Vec<Assignment>, results: &mut Vec<Assignment>) {
-        let mut rank: i32 = 0;
-        let task_count: usize = tasks.len();
+    // Hands out the tasks as the workers ask for work. With stealing, once the tasks run out
+    // the worker that has been busy the longest is asked to split its task into as many pieces
+    // as workers are left waiting. The pieces come back as tasks of their own, below
+    // placeholders for their nodes in the tree, and go to the waiting workers. Requests to
+    // split still open after a failure are answered before the error is returned. A worker
+    // that finished its task before it saw the request first sends its result and asks for
+    // work, and only answers from there, so everything it sends until then is dropped.
+    fn send_requests<G: Game>(
+        &mut self,
+        game: &G,
+        tasks: &mut Vec<Assignment<G>>,
+        results: &mut Vec<Assignment<G>>,
+        tree: &mut Tree,
+    ) -> Result<(), ProcessError> {
+        let mut stealing: Vec<(i32, usize)> = vec![];
+        let result: Result<(), ProcessError> =
+            self.hand_out_tasks(game, tasks, results, tree, &mut stealing);
 
-        loop {
-            if task_count == 0 {
-                break;
+        for (rank, _) in stealing {
+            loop {
+                let (_, status) = self.world.process_at_rank(rank).receive_vec::<u8>();
+
+                match Tag::from_i32(status.tag()) {
+                    Some(Tag::Pieces) => break,
+                    Some(Tag::Request) => self.idle.push(rank),
+                    _ => (),
+                }
             }
+        }
 
-            if rank == self.world.rank() {
-                rank += 1;
-                continue;
+        return result;
+    }
+
+    fn hand_out_tasks<G: Game>(
+        &mut self,
+        game: &G,
+        tasks: &mut Vec<Assignment<G>>,
+        results: &mut Vec<Assignment<G>>,
+        tree: &mut Tree,
+        stealing: &mut Vec<(i32, usize)>,
+    ) -> Result<(), ProcessError> {
+        let mut task_count: usize = tasks.len();
+        let mut busy: BTreeMap<i32, Instant> = BTreeMap::new();
+        let mut idle_since: Option<Instant> = None;
+
+        if task_count == 0 {
+            return Ok(());
+        }
+
+        for idle in std::mem::take(&mut self.idle) {
+            match tasks.pop() {
+                Some(task) => {
+                    self.send_task(idle, &task)?;
+                    busy.insert(idle, Instant::now());
+                }
+                None => self.idle.push(idle),
             }
+        }
 
-            if rank == self.world.size() {
-                rank = 0;
-                continue;
+        // Requests to split that are still open are answered, if only with no pieces.
+        while results.len() < task_count || !stealing.is_empty() {
+            if !self.idle.is_empty() && tasks.is_empty() {
+                idle_since.get_or_insert_with(Instant::now);
             }
 
-            let (msg, status) = self.world.process_at_rank(rank).receive_vec::<u8>();
+            let requested: usize = stealing.iter().map(|x| x.1).sum();
 
-            if status.tag() == Tag::Request as i32 {
-                let task: Assignment = tasks.pop().unwrap();
+            if self.steal && tasks.is_empty() && requested < self.idle.len() {
+                let victim: Option<i32> = busy
+                    .iter()
+                    .filter(|x| !stealing.iter().any(|y| y.0 == *x.0))
+                    .min_by_key(|x| *x.1)
+                    .map(|x| *x.0);
+
+                if let Some(victim) = victim {
+                    let wanted: usize = self.idle.len() - requested;
 
-                self.world
-                    .process_at_rank(rank)
-                    .send_with_tag(&bincode::serialize(&task).unwrap(), Tag::Response as i32);
+                    self.world
+                        .process_at_rank(victim)
+                        .send_with_tag(&bincode::serialize(&wanted)?, Tag::Steal as i32);
 
-                if tasks.len() == 0 {
-                    rank = 0;
+                    stealing.push((victim, wanted));
+                    self.balance.steals += 1;
                     continue;
                 }
-            } else {
-                results.push(bincode::deserialize(&msg).unwrap());
+            }
 
-                if results.len() == task_count {
-                    break;
+            let (msg, status) = self.world.any_process().receive_vec::<u8>();
+            let rank: i32 = status.source_rank();
+
+            match Tag::from_i32(status.tag()) {
+                Some(Tag::Request) => {
+                    let Some(task) = tasks.pop() else {
+                        self.idle.push(rank);
+                        continue;
+                    };
+
+                    self.send_task(rank, &task)?;
+                    busy.insert(rank, Instant::now());
+                }
+                Some(Tag::Result) => {
+                    let result: Assignment<G> = bincode::deserialize(&msg)?;
+                    let worker: &mut WorkerReport =
+                        self.workers.entry(rank).or_insert(WorkerReport {
+                            rank,
+                            ..WorkerReport::default()
+                        });
+
+                    worker.tasks += 1;
+                    worker.nodes += result.stats.nodes;
+                    worker.busy += result.stats.elapsed.as_secs_f64();
+
+                    busy.remove(&rank);
+                    results.push(result);
+                }
+                Some(Tag::Pieces) => {
+                    let pieces: Vec<Assignment<G>> = bincode::deserialize(&msg)?;
+                    stealing.retain(|x| x.0 != rank);
+
+                    for mut piece in pieces {
+                        piece.target = place_piece(game, tree, &piece)?;
+                        task_count += 1;
+                        self.balance.pieces += 1;
+
+                        match self.idle.pop() {
+                            Some(idle) => {
+                                self.send_task(idle, &piece)?;
+                                busy.insert(idle, Instant::now());
+                            }
+                            None => tasks.push(piece),
+                        }
+                    }
                 }
+                _ => return Err(rank_error(rank, &status, &msg)),
             }
+        }
 
-            rank += 1;
+        if let Some(since) = idle_since {
+            self.balance.tail += since.elapsed().as_secs_f64();
         }
+
+        return Ok(());
     }
 
-    fn calculate_best_move(&mut self, root: &mut Node, responses: Vec<Assignment>) -> usize {
+    fn send_task<G: Game>(&mut self, rank: i32, task: &Assignment<G>) -> Result<(), ProcessError> {
+        self.world
+            .process_at_rank(rank)
+            .send_with_tag(&bincode::serialize(task)?, Tag::Response as i32);
+
+        return Ok(());
+    }
+
+    fn calculate_best_move<G: Game>(
+        &mut self,
+        game: &mut G,
+        tree: &mut Tree,
+        color: TokenColor,
+        responses: Vec<Assignment<G>>,
+        duplicates: Vec<Duplicate>,
+    ) -> Result<usize, ProcessError> {
         for result in responses {
-            let (i, j) = result.indexes;
-            root.children[i].children[j] = result.node;
-        }
+            self.stats += result.stats;
 
-        let mut best_move: Option<usize> = None;
-        let mut max_value: Option<f64> = None;
+            if tree.node(result.target).is_none() {
+                return Err(ProcessError::Protocol(format!(
+                    "result for unknown task {}",
+                    result.target
+                )));
+            }
 
-        for child in root.children.iter_mut() {
-            child.calculate_value(&mut self.board, self.cpu_color, self.player_color);
+            // Pieces split off from the task already hang below its node
+            tree.merge(result.target, &result.tree);
+        }
 
-            if best_move == None || child.value > max_value {
-                best_move = Some(child.column);
-                max_value = child.value;
+        for duplicate in duplicates {
+            let mut subtree: Tree = tree.subtree(duplicate.source);
+
+            if duplicate.mirrored {
+                subtree.mirror(game);
             }
+
+            tree.graft(duplicate.target, &subtree);
         }
 
-        return best_move.unwrap();
+        tree.calculate_value(ROOT, game, color, self.backup);
+
+        let best: NodeId = tree.best_child(ROOT, color).unwrap();
+
+        return Ok(tree.node(best).unwrap().column);
     }
 
-    fn notify_game_finished(&mut self) {
+    // Also called after a failure, when workers may still be busy with tasks of an abandoned
+    // search, their results are received and dropped before they are told to finish. Workers
+    // still in a Lazy SMP search are stopped at their next exchange. With a distributed table
+    // a busy worker may still need answers from the others, so all of them are done before
+    // the first one is told to finish.
+    pub fn notify_game_finished(&mut self) {
         for rank in 0..self.world.size() {
-            if rank == self.world.rank() {
+            if rank == self.world.rank() || self.idle.contains(&rank) {
                 continue;
             }
 
-            self.world
-                .process_at_rank(rank)
-                .receive_vec_with_tag::<u8>(Tag::Request as i32);
+            loop {
+                let (_, status) = self.world.process_at_rank(rank).receive_vec::<u8>();
+
+                if status.tag() == Tag::Request as i32 {
+                    break;
+                }
+
+                if status.tag() == Tag::Entries as i32 {
+                    self.world
+                        .process_at_rank(rank)
+                        .send_with_tag::<u8>(&0, Tag::Stop as i32);
+                }
+            }
+        }
+
+        for rank in 0..self.world.size() {
+            if rank == self.world.rank() {
+                continue;
+            }
 
             self.world
                 .process_at_rank(rank)
                 .send_with_tag(&0, Tag::Finished as i32);
         }
+
+        self.idle.clear();
+    }
+}
+
+// The error of a rank that reported a failure, or sent a message that does not belong here.
+fn rank_error(rank: i32, status: &Status, msg: &[u8]) -> ProcessError {
+    if let Some(Tag::Error) = Tag::from_i32(status.tag()) {
+        return ProcessError::Protocol(format!(
+            "rank {} failed: {}",
+            rank,
+            String::from_utf8_lossy(msg)
+        ));
     }
+
+    return ProcessError::Protocol(format!(
+        "unexpected tag {} from rank {}",
+        status.tag(),
+        rank
+    ));
+}
+
+// Hangs the node of a piece into the tree, below the node of the task it was split off from,
+// with placeholders for the nodes in between. Returns the node of the piece.
+fn place_piece<G: Game>(
+    game: &G,
+    tree: &mut Tree,
+    piece: &Assignment<G>,
+) -> Result<NodeId, ProcessError> {
+    let Some(task) = tree.node(piece.target) else {
+        return Err(ProcessError::Protocol(format!(
+            "piece of unknown task {}",
+            piece.target
+        )));
+    };
+
+    let mut color: TokenColor = task.color;
+    let line: Vec<usize> = piece.line.iter().map(|&x| game.encode(x)).collect();
+    let depth: usize = tree.path(piece.target).len();
+
+    if line.len() <= depth {
+        return Err(ProcessError::Protocol(format!(
+            "piece outside of task {}",
+            piece.target
+        )));
+    }
+
+    let mut node: NodeId = piece.target;
+
+    for &column in &line[depth..line.len() - 1] {
+        color = color.invert();
+        node = match tree.child(node, column) {
+            Some(child) => child,
+            None => tree.add_child(node, &Node::new(color, column, GameStatus::InProgress)),
+        };
+    }
+
+    return Ok(tree.add_child(node, &piece.tree.to_node(ROOT)));
 }
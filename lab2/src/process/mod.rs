@@ -1,4 +1,15 @@
 pub mod assignment;
+pub mod bench;
+pub mod book;
+pub mod clock;
+pub mod command;
+pub mod distributed;
+pub mod error;
+pub mod lazy;
+pub mod level;
 pub mod master;
+pub mod player;
+pub mod record;
+pub mod report;
 pub mod tag;
 pub mod worker;
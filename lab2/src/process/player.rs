@@ -0,0 +1,15 @@
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Player {
+    Human,
+    Engine,
+}
+
+impl Player {
+    pub fn parse(input: &str) -> Option<Player> {
+        return match input {
+            "human" => Some(Player::Human),
+            "engine" => Some(Player::Engine),
+            _ => None,
+        };
+    }
+}
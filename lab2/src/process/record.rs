@@ -0,0 +1,56 @@
+use crate::board::{board::Move, notation::format_line, token::TokenColor};
+use std::{fmt, fs, io};
+
+pub struct GameRecord {
+    pub first: TokenColor,
+    pub moves: Vec<Move>,
+    pub winner: Option<TokenColor>,
+    pub lines: Vec<Vec<(usize, usize)>>,
+}
+
+impl GameRecord {
+    pub fn new(
+        first: TokenColor,
+        moves: Vec<Move>,
+        winner: Option<TokenColor>,
+        lines: Vec<Vec<(usize, usize)>>,
+    ) -> GameRecord {
+        return Self {
+            first,
+            moves,
+            winner,
+            lines,
+        };
+    }
+
+    pub fn save(self: &Self, path: &str) -> io::Result<()> {
+        return fs::write(path, self.to_string());
+    }
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        writeln!(f, "first: {:?}", self.first)?;
+        writeln!(
+            f,
+            "moves: {}",
+            self.moves
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<String>>()
+                .join(" ")
+        )?;
+
+        match self.winner {
+            Some(color) => writeln!(f, "winner: {:?}", color)?,
+            None => writeln!(f, "winner: -")?,
+        }
+
+        // (row, column) cells of every winning line, rows counted from the top
+        for line in self.lines.iter() {
+            writeln!(f, "line: {}", format_line(line))?;
+        }
+
+        return Ok(());
+    }
+}
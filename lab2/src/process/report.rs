@@ -0,0 +1,170 @@
+use crate::board::{
+    notation::{format_line, format_moves},
+    token::TokenColor,
+};
+use crate::node::{
+    score::{decided_at, format_score},
+    stats::TableStats,
+};
+use serde::Serialize;
+use std::{fmt, time::Duration};
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct WorkerReport {
+    pub rank: i32,
+    pub tasks: usize,
+    pub nodes: u64,
+    pub busy: f64,
+}
+
+// How evenly the tasks of the split search kept the workers busy.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct LoadBalance {
+    // Requests to split a running task, and the tasks split off by them.
+    pub steals: usize,
+    pub pieces: usize,
+    // Seconds from the first worker running out of work to the last result.
+    pub tail: f64,
+}
+
+// Everything measured during one engine search, printed after the move and written as one
+// JSON line per search for the scaling analysis.
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchReport {
+    pub ply: usize,
+    pub color: TokenColor,
+    pub ranks: i32,
+    pub scores: Vec<(usize, f64)>,
+    pub best_move: usize,
+    pub principal_variation: Vec<usize>,
+    pub nodes: u64,
+    pub cutoffs: u64,
+    pub table_hits: u64,
+    pub table: TableStats,
+    pub balance: LoadBalance,
+    pub max_depth: usize,
+    pub elapsed: f64,
+    pub nodes_per_second: f64,
+    pub workers: Vec<WorkerReport>,
+    // Size of the master's tree after the worker results were grafted onto it.
+    pub tree_nodes: usize,
+    pub tree_memory: usize,
+    // Set when the searched move won the game.
+    pub winning_lines: Vec<Vec<(usize, usize)>>,
+}
+
+impl SearchReport {
+    pub fn elapsed(self: &Self) -> Duration {
+        return Duration::from_secs_f64(self.elapsed);
+    }
+
+    pub fn to_json(self: &Self) -> String {
+        return serde_json::to_string(self).unwrap();
+    }
+
+    // "win in N" or "loss in N" for the side to move once the search proved the result.
+    pub fn outcome(self: &Self) -> Option<String> {
+        let score: f64 = self.scores.iter().find(|x| x.0 == self.best_move)?.1;
+
+        if decided_at(score).is_none() {
+            return None;
+        }
+
+        return Some(format_score(score, self.ply));
+    }
+
+    pub fn summary(self: &Self) -> String {
+        return format!(
+            "PV {}  nodes {}  {:.0} nodes/s  cutoffs {}  depth {}",
+            format_moves(&self.principal_variation),
+            self.nodes,
+            self.nodes_per_second,
+            self.cutoffs,
+            self.max_depth
+        );
+    }
+
+    pub fn worker_summary(self: &Self) -> String {
+        let workers: String = self
+            .workers
+            .iter()
+            .map(|x| format!("{}: {} tasks {:.3}s", x.rank, x.tasks, x.busy))
+            .collect::<Vec<String>>()
+            .join("  ");
+
+        if self.balance.tail == 0.0 {
+            return workers;
+        }
+
+        return format!("{}  tail {:.3}s", workers, self.balance.tail);
+    }
+}
+
+impl fmt::Display for SearchReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        writeln!(
+            f,
+            "Principal variation: {}",
+            self.principal_variation
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<String>>()
+                .join(" ")
+        )?;
+        if let Some(outcome) = self.outcome() {
+            writeln!(f, "Outcome: {} for {:?}", outcome, self.color)?;
+        }
+
+        writeln!(
+            f,
+            "Nodes: {} ({:.0} nodes/s), cutoffs: {}, max depth: {}",
+            self.nodes, self.nodes_per_second, self.cutoffs, self.max_depth
+        )?;
+
+        if self.table_hits > 0 {
+            writeln!(f, "Table hits: {}", self.table_hits)?;
+        }
+
+        if self.table.remote_probes > 0 {
+            writeln!(
+                f,
+                "Distributed table: {} remote probes, {:.1}% hits, {} messages ({:.1} KiB), \
+                 {:.3}s waiting",
+                self.table.remote_probes,
+                self.table.hit_rate() * 100.0,
+                self.table.messages,
+                self.table.bytes as f64 / 1024.0,
+                self.table.wait.as_secs_f64()
+            )?;
+        }
+
+        writeln!(
+            f,
+            "Tree: {} nodes, {:.1} KiB",
+            self.tree_nodes,
+            self.tree_memory as f64 / 1024.0
+        )?;
+
+        if self.balance.tail > 0.0 {
+            writeln!(
+                f,
+                "Tail: {:.3}s after the first worker ran out of work, {} steals, {} pieces",
+                self.balance.tail, self.balance.steals, self.balance.pieces
+            )?;
+        }
+
+        for line in self.winning_lines.iter() {
+            writeln!(f, "Winning line: {}", format_line(line))?;
+        }
+
+        for worker in self.workers.iter() {
+            writeln!(
+                f,
+                "Rank {}: {} tasks, {} nodes, busy {:.3}s",
+                worker.rank, worker.tasks, worker.nodes, worker.busy
+            )?;
+        }
+
+        return Ok(());
+    }
+}
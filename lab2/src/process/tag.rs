@@ -3,4 +3,42 @@ pub enum Tag {
     Response = 1,
     Result = 2,
     Finished = 3,
+    // A worker could not handle its assignment, the message is the error text.
+    Error = 4,
+    // Root position of a Lazy SMP search, which the worker searches until it is stopped.
+    Lazy = 5,
+    // Table entries sent between the ranks of a Lazy SMP search.
+    Entries = 6,
+    Stop = 7,
+    // Lookups and stores of the distributed table, sent between workers.
+    TableProbe = 8,
+    TableReply = 9,
+    TableStore = 10,
+    // The master asks a busy worker to split its task, the worker answers with the pieces.
+    Steal = 11,
+    Pieces = 12,
+    // The owner confirms the last batch of table stores of a task.
+    TableStored = 13,
+}
+
+impl Tag {
+    pub fn from_i32(tag: i32) -> Option<Tag> {
+        return match tag {
+            0 => Some(Tag::Request),
+            1 => Some(Tag::Response),
+            2 => Some(Tag::Result),
+            3 => Some(Tag::Finished),
+            4 => Some(Tag::Error),
+            5 => Some(Tag::Lazy),
+            6 => Some(Tag::Entries),
+            7 => Some(Tag::Stop),
+            8 => Some(Tag::TableProbe),
+            9 => Some(Tag::TableReply),
+            10 => Some(Tag::TableStore),
+            11 => Some(Tag::Steal),
+            12 => Some(Tag::Pieces),
+            13 => Some(Tag::TableStored),
+            _ => None,
+        };
+    }
 }
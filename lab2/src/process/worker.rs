@@ -1,60 +1,406 @@
-use super::assignment::Assignment;
-use crate::{board::token::TokenColor, process::tag::Tag};
-use mpi::{topology::*, traits::*};
+use super::{
+    assignment::Assignment,
+    distributed::DistributedTable,
+    error::ProcessError,
+    lazy::{helper, LazyTask, EXCHANGE_INTERVAL},
+};
+use crate::{
+    board::{board::GameStatus, token::TokenColor},
+    game::game::Game,
+    node::{
+        arena::{NodeId, Tree, ROOT},
+        backup::Backup,
+        node::{Node, Piece, SplitPoints},
+        ordering::{MoveOrdering, OrderingOptions},
+        stats::SearchStats,
+        table::{Entry, SharedTable},
+    },
+    process::tag::Tag,
+};
+use mpi::{point_to_point::Status, topology::*, traits::*};
+use std::{cell::RefCell, rc::Rc, sync::Arc, thread, time::Instant};
+
+// Moves the search makes between two looks for a request of the master to split the task.
+const STEAL_POLL_INTERVAL: u64 = 256;
 
 pub struct Worker {
     world: SystemCommunicator,
     master_rank: i32,
-    cpu_color: TokenColor,
-    player_color: TokenColor,
-    depth: usize,
+    options: OrderingOptions,
+    ordering: MoveOrdering,
+    table: Option<Rc<RefCell<DistributedTable>>>,
 }
 
 impl Worker {
-    pub fn new(world: SystemCommunicator, master_rank: i32, depth: usize) -> Self {
+    pub fn new(
+        world: SystemCommunicator,
+        master_rank: i32,
+        ordering: OrderingOptions,
+        distributed: bool,
+    ) -> Self {
+        let table: Option<Rc<RefCell<DistributedTable>>> = match distributed {
+            true => Some(Rc::new(RefCell::new(DistributedTable::new(
+                world,
+                master_rank,
+            )))),
+            false => None,
+        };
+
         return Self {
             world,
             master_rank,
-            cpu_color: TokenColor::Red,
-            player_color: TokenColor::Yellow,
-            depth,
+            options: ordering,
+            ordering: match &table {
+                Some(table) => MoveOrdering::with_table(ordering, Box::new(table.clone()), 0),
+                None => MoveOrdering::new(ordering),
+            },
+            table,
         };
     }
 
-    pub fn run(self: &Self) {
+    // Failures are reported to the master, which decides when to stop, so the worker keeps
+    // serving requests until it is told to finish and only then returns the first error. The
+    // game has to be the one the master plays, the positions would not deserialize otherwise.
+    pub fn run<G: Game>(self: &mut Self) -> Result<(), ProcessError> {
+        let mut failure: Option<ProcessError> = None;
+
         loop {
             self.world
                 .process_at_rank(self.master_rank)
                 .send_with_tag::<u8>(&0, Tag::Request as i32);
 
+            let (msg, status) = self.receive_work::<G>()?;
+
+            let result: Result<Vec<u8>, ProcessError> = match Tag::from_i32(status.tag()) {
+                Some(Tag::Finished) => break,
+                Some(Tag::Response) => self.search::<G>(&msg),
+                Some(Tag::Lazy) => self.lazy_search::<G>(&msg),
+                _ => Err(ProcessError::Protocol(format!(
+                    "unexpected tag {} from the master",
+                    status.tag()
+                ))),
+            };
+
+            match result {
+                Ok(task_encoded) => self
+                    .world
+                    .process_at_rank(self.master_rank)
+                    .send_with_tag(&task_encoded, Tag::Result as i32),
+                Err(e) => {
+                    self.world
+                        .process_at_rank(self.master_rank)
+                        .send_with_tag(e.to_string().as_bytes(), Tag::Error as i32);
+
+                    failure.get_or_insert(e);
+                }
+            }
+        }
+
+        return match failure {
+            Some(e) => Err(e),
+            None => Ok(()),
+        };
+    }
+
+    fn search<G: Game>(self: &mut Self, msg: &[u8]) -> Result<Vec<u8>, ProcessError> {
+        let mut assignment: Assignment<G> = bincode::deserialize(msg)?;
+        let root: G = assignment.board.clone();
+
+        if assignment.tree.node(ROOT).is_none() {
+            return Err(ProcessError::Protocol("task without a node".to_string()));
+        }
+
+        let line: Vec<usize> = assignment
+            .line
+            .iter()
+            .map(|&x| assignment.board.encode(x))
+            .collect();
+        assignment.board.play_line(&line, assignment.color)?;
+
+        let start = Instant::now();
+        let mut splitter: Splitter<G> =
+            Splitter::new(&self.world, self.master_rank, &assignment, root);
+
+        let mut node: Node = assignment.tree.to_node(ROOT);
+        node.search_split(
+            &mut assignment.board,
+            assignment.depth,
+            0,
+            assignment.color,
+            assignment.backup,
+            &mut self.ordering,
+            &mut assignment.stats,
+            &mut SplitPoints::default(),
+            &mut |points| splitter.poll(points),
+        );
+
+        assignment.stats.elapsed = start.elapsed();
+        assignment.tree = Tree::from_node(&node);
+
+        // A request to split is answered even if the search failed, the master waits for it.
+        let split: Result<(), ProcessError> = splitter.finish();
+
+        if let Some(e) = self
+            .table
+            .as_ref()
+            .and_then(|x| x.borrow_mut().take_failure())
+        {
+            return Err(e);
+        }
+
+        split?;
+
+        if let Some(table) = &self.table {
+            let mut table = table.borrow_mut();
+            table.flush()?;
+            assignment.stats.table = table.take_stats();
+        }
+
+        return Ok(bincode::serialize(&assignment)?);
+    }
+
+    // With a distributed table the other workers may need answers while this one waits. A
+    // request to split that comes after the task was done gets no pieces.
+    fn receive_work<G: Game>(self: &Self) -> Result<(Vec<u8>, Status), ProcessError> {
+        loop {
+            if let Some(table) = &self.table {
+                loop {
+                    let status: Status = self.world.any_process().probe();
+
+                    if status.source_rank() == self.master_rank {
+                        break;
+                    }
+
+                    match Tag::from_i32(status.tag()) {
+                        Some(Tag::TableProbe) | Some(Tag::TableStore) => {
+                            table.borrow_mut().serve()?
+                        }
+                        _ => {
+                            return Err(ProcessError::Protocol(format!(
+                                "unexpected tag {} from rank {}",
+                                status.tag(),
+                                status.source_rank()
+                            )))
+                        }
+                    }
+                }
+            }
+
             let (msg, status) = self
                 .world
                 .process_at_rank(self.master_rank)
                 .receive_vec::<u8>();
 
-            if status.tag() == Tag::Finished as i32 {
-                break;
+            if status.tag() != Tag::Steal as i32 {
+                return Ok((msg, status));
             }
 
-            if status.tag() == Tag::Response as i32 {
-                let mut assignment: Assignment = bincode::deserialize(&msg).unwrap();
+            self.world.process_at_rank(self.master_rank).send_with_tag(
+                &bincode::serialize(&Vec::<Assignment<G>>::new())?,
+                Tag::Pieces as i32,
+            );
+        }
+    }
 
-                assignment
-                    .node
-                    .build_tree(&mut assignment.board, self.depth, 0);
+    // Runs the helper threads of a Lazy SMP search and trades table entries with the master
+    // until it says stop, then answers with the statistics of all helpers.
+    fn lazy_search<G: Game>(self: &mut Self, msg: &[u8]) -> Result<Vec<u8>, ProcessError> {
+        let task: LazyTask<G> = bincode::deserialize(msg)?;
+        let table: Arc<SharedTable> = Arc::new(SharedTable::new());
+        let options: OrderingOptions = self.options;
 
-                assignment.node.calculate_value(
-                    &mut assignment.board,
-                    self.cpu_color,
-                    self.player_color,
-                );
+        let stats: SearchStats = thread::scope(|scope| {
+            let helpers: Vec<_> = (0..task.threads)
+                .map(|i| {
+                    let id: usize = task.first_helper + i;
+                    let task: LazyTask<G> = task.clone();
+                    let table: Arc<SharedTable> = table.clone();
 
-                let task_encoded: Vec<u8> = bincode::serialize(&assignment).unwrap();
+                    scope.spawn(move || helper(task, id, table, options))
+                })
+                .collect();
 
-                self.world
-                    .process_at_rank(self.master_rank)
-                    .send_with_tag(&task_encoded, Tag::Result as i32);
+            let result: Result<(), ProcessError> = self.exchange(&table);
+            table.stop();
+
+            let mut stats: SearchStats = SearchStats::default();
+
+            for handle in helpers {
+                stats += handle.join().unwrap();
+            }
+
+            return result.map(|_| stats);
+        })?;
+
+        return Ok(bincode::serialize(&stats)?);
+    }
+
+    fn exchange(self: &Self, table: &SharedTable) -> Result<(), ProcessError> {
+        loop {
+            thread::sleep(EXCHANGE_INTERVAL);
+
+            self.world.process_at_rank(self.master_rank).send_with_tag(
+                &bincode::serialize(&table.take_fresh())?,
+                Tag::Entries as i32,
+            );
+
+            let (msg, status) = self
+                .world
+                .process_at_rank(self.master_rank)
+                .receive_vec::<u8>();
+
+            match Tag::from_i32(status.tag()) {
+                Some(Tag::Entries) => table.merge(&bincode::deserialize::<Vec<Entry>>(&msg)?),
+                Some(Tag::Stop) => return Ok(()),
+                _ => {
+                    return Err(ProcessError::Protocol(format!(
+                        "unexpected tag {} from the master",
+                        status.tag()
+                    )))
+                }
             }
         }
     }
 }
+
+// Answers the master's requests to split the task being searched. A request asks for a number
+// of pieces, it is kept until the search gets to a node with moves left to give and answered
+// with what there is. Moves are given from the highest nodes first, down to the ones whose
+// moves still have something below them to search. A request still open when the search ends
+// gets no pieces.
+struct Splitter<'a, G: Game> {
+    world: &'a SystemCommunicator,
+    master_rank: i32,
+    root: G,
+    line: Vec<G::Move>,
+    color: TokenColor,
+    depth: usize,
+    backup: Backup,
+    target: NodeId,
+    polls: u64,
+    wanted: Option<usize>,
+    failure: Option<ProcessError>,
+}
+
+impl<'a, G: Game> Splitter<'a, G> {
+    fn new(
+        world: &'a SystemCommunicator,
+        master_rank: i32,
+        task: &Assignment<G>,
+        root: G,
+    ) -> Splitter<'a, G> {
+        return Self {
+            world,
+            master_rank,
+            root,
+            line: task.line.clone(),
+            color: task.color,
+            depth: task.depth,
+            backup: task.backup,
+            target: task.target,
+            polls: 0,
+            wanted: None,
+            failure: None,
+        };
+    }
+
+    fn poll(self: &mut Self, points: &mut SplitPoints) {
+        if self.failure.is_some() {
+            return;
+        }
+
+        if self.wanted.is_none() {
+            self.polls += 1;
+
+            if self.polls % STEAL_POLL_INTERVAL != 0 {
+                return;
+            }
+
+            match steal_requested(self.world, self.master_rank) {
+                Ok(wanted) => self.wanted = wanted,
+                Err(e) => self.failure = Some(e),
+            }
+        }
+
+        let Some(count) = self.wanted else {
+            return;
+        };
+
+        let pieces: Vec<Piece> = points.take(count, self.depth);
+
+        if pieces.is_empty() {
+            return;
+        }
+
+        if let Err(e) = self.answer(pieces) {
+            self.failure = Some(e);
+        }
+    }
+
+    fn finish(mut self: Self) -> Result<(), ProcessError> {
+        if self.wanted.is_some() {
+            self.answer(vec![])?;
+        }
+
+        return match self.failure {
+            Some(e) => Err(e),
+            None => Ok(()),
+        };
+    }
+
+    // The pieces become tasks of their own, which the master hangs below the node of the task
+    // by the moves that lead to them.
+    fn answer(self: &mut Self, pieces: Vec<Piece>) -> Result<(), ProcessError> {
+        self.wanted = None;
+
+        let mut tasks: Vec<Assignment<G>> = vec![];
+
+        for piece in pieces {
+            let mut game: G = self.root.clone();
+            let mut line: Vec<G::Move> = self.line.clone();
+            line.extend(piece.path.iter().map(|&x| game.decode(x)));
+
+            let moves: Vec<usize> = line.iter().map(|&x| game.encode(x)).collect();
+            game.play_line(&moves, self.color)?;
+            let status: GameStatus = game.apply(piece.column, piece.color)?;
+            line.push(game.decode(piece.column));
+
+            tasks.push(Assignment::new(
+                Tree::from_node(&Node::new(piece.color, piece.column, status)),
+                self.root.clone(),
+                line,
+                self.color,
+                self.depth - piece.path.len() - 1,
+                self.backup,
+                self.target,
+            ));
+        }
+
+        self.world
+            .process_at_rank(self.master_rank)
+            .send_with_tag(&bincode::serialize(&tasks)?, Tag::Pieces as i32);
+
+        return Ok(());
+    }
+}
+
+// Takes the master's request to split the task, if one came in, with the number of pieces it
+// asks for.
+fn steal_requested(
+    world: &SystemCommunicator,
+    master_rank: i32,
+) -> Result<Option<usize>, ProcessError> {
+    if world
+        .process_at_rank(master_rank)
+        .immediate_probe_with_tag(Tag::Steal as i32)
+        .is_none()
+    {
+        return Ok(None);
+    }
+
+    let (msg, _) = world
+        .process_at_rank(master_rank)
+        .receive_vec_with_tag::<u8>(Tag::Steal as i32);
+
+    return Ok(Some(bincode::deserialize(&msg)?));
+}
@@ -0,0 +1,3 @@
+pub mod plain;
+pub mod terminal;
+pub mod ui;
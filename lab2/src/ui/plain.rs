@@ -0,0 +1,143 @@
+use super::ui::Ui;
+use crate::{
+    board::board::{Board, Move},
+    board::token::TokenColor,
+    node::score::format_score,
+    process::clock::Clock,
+    process::command::Command,
+    process::report::SearchReport,
+};
+use std::{
+    io::{self, Write},
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::{Duration, Instant},
+};
+
+pub struct PlainUi {
+    // Lines of the standard input, read by a thread of their own so that waiting for them can
+    // time out.
+    lines: Option<Receiver<String>>,
+}
+
+impl PlainUi {
+    pub fn new() -> Self {
+        return Self { lines: None };
+    }
+
+    // Returns None at the end of the input, or when no line came within `limit`.
+    fn read_line(self: &mut Self, limit: Option<Duration>) -> Option<String> {
+        let lines: &Receiver<String> = self.lines.get_or_insert_with(|| {
+            let (sender, receiver) = mpsc::channel();
+
+            thread::spawn(move || loop {
+                let mut input_line: String = String::new();
+
+                // A line that could not be read is passed on empty, as invalid input
+                match io::stdin().read_line(&mut input_line) {
+                    Ok(0) => break,
+                    Ok(_) => (),
+                    Err(_) => input_line.clear(),
+                }
+
+                if sender.send(input_line).is_err() {
+                    break;
+                }
+            });
+
+            receiver
+        });
+
+        return match limit {
+            Some(limit) => lines.recv_timeout(limit).ok(),
+            None => lines.recv().ok(),
+        };
+    }
+}
+
+impl Ui for PlainUi {
+    fn read_command(
+        &mut self,
+        _board: &Board,
+        _color: TokenColor,
+        limit: Option<Duration>,
+    ) -> Option<Command> {
+        let deadline: Option<Instant> = limit.map(|x| Instant::now() + x);
+
+        loop {
+            print!(
+                "Enter column index, p<column> to pop, or command \
+                (undo, hint, show, save <file>, resign, quit): "
+            );
+            io::stdout().flush().unwrap();
+
+            let left: Option<Duration> =
+                deadline.map(|x| x.saturating_duration_since(Instant::now()));
+            let input_line: String = self.read_line(left)?;
+
+            let Some(command) = Command::parse(&input_line) else {
+                println!("Invalid input");
+                continue;
+            };
+
+            return Some(command);
+        }
+    }
+
+    fn show_board(&mut self, board: &Board, history: &[usize]) {
+        board.show();
+        println!(
+            "Moves: {}",
+            history
+                .iter()
+                .map(|&x| Move::from_index(x, board.columns).to_string())
+                .collect::<Vec<String>>()
+                .join(" ")
+        );
+    }
+
+    fn show_move(&mut self, board: &Board, _history: &[usize]) {
+        board.show();
+    }
+
+    fn show_report(&mut self, report: &SearchReport) {
+        println!(
+            "{}",
+            report
+                .scores
+                .iter()
+                .map(|(_, value)| format_score(*value, report.ply))
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+        println!("Best move: {}", report.best_move);
+        println!("Elapsed time: {:#?}", report.elapsed());
+        print!("{}", report);
+    }
+
+    fn show_clock(&mut self, clock: &Clock) {
+        println!(
+            "Time left: Yellow {:.1?}, Red {:.1?}",
+            clock.remaining(TokenColor::Yellow),
+            clock.remaining(TokenColor::Red)
+        );
+    }
+
+    fn message(&mut self, text: &str) {
+        println!("{}", text);
+    }
+
+    fn pause(&mut self, _board: &Board, text: &str) -> bool {
+        print!("{}", text);
+        io::stdout().flush().unwrap();
+
+        return match self.read_line(None) {
+            Some(input_line) => input_line.trim() != "quit",
+            None => false,
+        };
+    }
+
+    fn game_over(&mut self, text: &str) {
+        println!("{}", text);
+    }
+}
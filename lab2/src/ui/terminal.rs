@@ -0,0 +1,359 @@
+use super::ui::Ui;
+use crate::{
+    board::board::{Board, Field, Move},
+    board::token::TokenColor,
+    node::score::format_score,
+    process::clock::Clock,
+    process::command::Command,
+    process::report::SearchReport,
+};
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, queue,
+    style::Print,
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use std::{
+    io::{self, IsTerminal, Stdout, Write},
+    thread,
+    time::{Duration, Instant},
+};
+
+const CELL_WIDTH: u16 = 3;
+const DROP_FRAME: Duration = Duration::from_millis(40);
+const KEYS_HELP: &str = "←/→ select, Enter drop, p pop, u undo, h hint, s save, r resign, q quit";
+
+pub struct TerminalUi {
+    stdout: Stdout,
+    board: Option<Board>,
+    falling: Option<(usize, usize, TokenColor)>,
+    history: Vec<usize>,
+    report: Option<SearchReport>,
+    clock: Option<(Duration, Duration)>,
+    cursor: Option<usize>,
+    selecting: bool,
+    status: String,
+    message: String,
+}
+
+impl TerminalUi {
+    // Fails without a terminal to draw on, like when the output is redirected.
+    pub fn new() -> io::Result<Self> {
+        let mut stdout: Stdout = io::stdout();
+
+        if !stdout.is_terminal() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "standard output is not a terminal",
+            ));
+        }
+
+        terminal::enable_raw_mode()?;
+
+        if let Err(e) = execute!(stdout, EnterAlternateScreen, Hide) {
+            let _ = terminal::disable_raw_mode();
+            return Err(e);
+        }
+
+        return Ok(Self {
+            stdout,
+            board: None,
+            falling: None,
+            history: vec![],
+            report: None,
+            clock: None,
+            cursor: None,
+            selecting: false,
+            status: String::new(),
+            message: String::new(),
+        });
+    }
+
+    fn draw(self: &mut Self) -> io::Result<()> {
+        let Some(board) = &self.board else {
+            return Ok(());
+        };
+
+        queue!(self.stdout, Clear(ClearType::All))?;
+
+        let header: String = (0..board.columns).map(|x| format!("{:<3}", x)).collect();
+        queue!(self.stdout, MoveTo(0, 0), Print(header))?;
+
+        if let (true, Some(column)) = (self.selecting, self.cursor) {
+            queue!(
+                self.stdout,
+                MoveTo(column as u16 * CELL_WIDTH, 1),
+                Print("▼")
+            )?;
+        }
+
+        let winning: Vec<(usize, usize)> = board.winning_cells();
+
+        for row in 0..board.rows {
+            let line: String = (0..board.columns)
+                .map(|column| match self.falling {
+                    Some((r, c, color)) if r == row && c == column => {
+                        format!("{} ", Field::Token(color))
+                    }
+                    _ if winning.contains(&(row, column)) => {
+                        format!("{} ", board.field(row, column).highlighted())
+                    }
+                    _ => format!("{} ", board.field(row, column)),
+                })
+                .collect();
+
+            queue!(self.stdout, MoveTo(0, row as u16 + 2), Print(line))?;
+        }
+
+        let x: u16 = board.columns as u16 * CELL_WIDTH + 4;
+        let moves: Vec<String> = self
+            .history
+            .chunks(2)
+            .enumerate()
+            .map(|(i, pair)| {
+                format!(
+                    "{:>2}. {}",
+                    i + 1,
+                    pair.iter()
+                        .map(|&x| Move::from_index(x, board.columns).to_string())
+                        .collect::<Vec<String>>()
+                        .join(" ")
+                )
+            })
+            .collect();
+        let visible: usize = board.rows + 1;
+
+        queue!(self.stdout, MoveTo(x, 0), Print("Moves"))?;
+        for (i, line) in moves
+            .iter()
+            .skip(moves.len().saturating_sub(visible))
+            .enumerate()
+        {
+            queue!(self.stdout, MoveTo(x, i as u16 + 1), Print(line))?;
+        }
+
+        let y: u16 = board.rows as u16 + 3;
+
+        if let Some(report) = &self.report {
+            let scores: String = report
+                .scores
+                .iter()
+                .map(|(column, value)| format!("{}:{}", column, format_score(*value, report.ply)))
+                .collect::<Vec<String>>()
+                .join("  ");
+
+            queue!(
+                self.stdout,
+                MoveTo(0, y),
+                Print(format!("Scores  {}", scores)),
+                MoveTo(0, y + 1),
+                Print(format!(
+                    "Best move: {}  Elapsed time: {:?}",
+                    report.best_move,
+                    report.elapsed()
+                )),
+                MoveTo(0, y + 2),
+                Print(report.summary()),
+                MoveTo(0, y + 3),
+                Print(format!("Workers  {}", report.worker_summary()))
+            )?;
+        }
+
+        if let Some((yellow, red)) = self.clock {
+            queue!(
+                self.stdout,
+                MoveTo(0, y + 4),
+                Print(format!("Time left  Yellow {:.1?}  Red {:.1?}", yellow, red))
+            )?;
+        }
+
+        queue!(
+            self.stdout,
+            MoveTo(0, y + 5),
+            Print(&self.status),
+            MoveTo(0, y + 6),
+            Print(&self.message)
+        )?;
+
+        return self.stdout.flush();
+    }
+
+    // Returns None when the input fails, or when no key was pressed before the deadline.
+    fn read_key(self: &mut Self, deadline: Option<Instant>) -> Option<KeyEvent> {
+        loop {
+            if let Some(deadline) = deadline {
+                let left: Duration = deadline.saturating_duration_since(Instant::now());
+
+                if !event::poll(left).unwrap_or(false) {
+                    return None;
+                }
+            }
+
+            match event::read() {
+                Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => return Some(key),
+                Ok(Event::Resize(_, _)) => self.draw().unwrap(),
+                Ok(_) => continue,
+                Err(_) => return None,
+            }
+        }
+    }
+
+    fn read_line(self: &mut Self, prompt: &str, deadline: Option<Instant>) -> Option<String> {
+        let status: String = self.status.clone();
+        let mut line: String = String::new();
+
+        let result: Option<String> = loop {
+            self.status = format!("{}{}", prompt, line);
+            self.draw().unwrap();
+
+            let Some(key) = self.read_key(deadline) else {
+                break None;
+            };
+
+            match key.code {
+                KeyCode::Enter if !line.is_empty() => break Some(line),
+                KeyCode::Esc => break None,
+                KeyCode::Backspace => {
+                    line.pop();
+                }
+                KeyCode::Char(c) => line.push(c),
+                _ => continue,
+            }
+        };
+
+        self.status = status;
+
+        return result;
+    }
+}
+
+impl Ui for TerminalUi {
+    fn read_command(
+        &mut self,
+        board: &Board,
+        color: TokenColor,
+        limit: Option<Duration>,
+    ) -> Option<Command> {
+        let deadline: Option<Instant> = limit.map(|x| Instant::now() + x);
+        self.board = Some(board.clone());
+        self.status = format!("{:?} to move | {}", color, KEYS_HELP);
+
+        let mut selected: usize = self.cursor.unwrap_or(board.columns / 2);
+        self.selecting = true;
+
+        let command: Option<Command> = loop {
+            self.cursor = Some(selected);
+            self.draw().unwrap();
+
+            let Some(key) = self.read_key(deadline) else {
+                break None;
+            };
+
+            match key.code {
+                KeyCode::Left if selected > 0 => selected -= 1,
+                KeyCode::Right if selected + 1 < board.columns => selected += 1,
+                KeyCode::Enter | KeyCode::Down | KeyCode::Char(' ') => {
+                    break Some(Command::Move(Move::Drop(selected)))
+                }
+                KeyCode::Char('p') => break Some(Command::Move(Move::Pop(selected))),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    break Some(Command::Quit)
+                }
+                KeyCode::Char(c) if c.is_ascii_digit() => {
+                    break Some(Command::Move(Move::Drop(c.to_digit(10).unwrap() as usize)))
+                }
+                KeyCode::Char('u') => break Some(Command::Undo),
+                KeyCode::Char('h') => break Some(Command::Hint),
+                KeyCode::Char('r') => break Some(Command::Resign),
+                KeyCode::Char('q') | KeyCode::Esc => break Some(Command::Quit),
+                KeyCode::Char('s') => {
+                    if let Some(path) = self.read_line("Save to: ", deadline) {
+                        break Some(Command::Save(path));
+                    }
+                }
+                _ => continue,
+            }
+        };
+
+        self.selecting = false;
+        self.message.clear();
+
+        return command;
+    }
+
+    fn show_board(&mut self, board: &Board, history: &[usize]) {
+        self.board = Some(board.clone());
+        self.history = history.to_vec();
+        self.draw().unwrap();
+    }
+
+    fn show_move(&mut self, board: &Board, history: &[usize]) {
+        self.history = history.to_vec();
+
+        let Some((row, column)) = board.last_move() else {
+            return self.show_board(board, history);
+        };
+
+        let Field::Token(color) = *board.field(row, column) else {
+            return self.show_board(board, history);
+        };
+
+        let mut frame: Board = board.clone();
+        frame.undo_move(column).unwrap();
+        self.board = Some(frame);
+        self.status = format!("{:?} to move", color.invert());
+
+        for r in 0..=row {
+            self.falling = Some((r, column, color));
+            self.draw().unwrap();
+            thread::sleep(DROP_FRAME);
+        }
+
+        self.falling = None;
+        self.show_board(board, history);
+    }
+
+    fn show_report(&mut self, report: &SearchReport) {
+        self.report = Some(report.clone());
+        self.draw().unwrap();
+    }
+
+    fn show_clock(&mut self, clock: &Clock) {
+        self.clock = Some((
+            clock.remaining(TokenColor::Yellow),
+            clock.remaining(TokenColor::Red),
+        ));
+        self.draw().unwrap();
+    }
+
+    fn message(&mut self, text: &str) {
+        self.message = text.to_string();
+        self.draw().unwrap();
+    }
+
+    fn pause(&mut self, board: &Board, text: &str) -> bool {
+        self.board = Some(board.clone());
+        self.status = format!("{} (q to quit)", text);
+        self.draw().unwrap();
+
+        return match self.read_key(None) {
+            Some(key) => !matches!(key.code, KeyCode::Char('q') | KeyCode::Esc),
+            None => false,
+        };
+    }
+
+    fn game_over(&mut self, text: &str) {
+        self.status = text.to_string();
+        self.message = "Press any key to exit".to_string();
+        self.draw().unwrap();
+        self.read_key(None);
+    }
+}
+
+impl Drop for TerminalUi {
+    fn drop(&mut self) {
+        let _ = execute!(self.stdout, LeaveAlternateScreen, Show);
+        let _ = terminal::disable_raw_mode();
+    }
+}
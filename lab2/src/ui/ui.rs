@@ -0,0 +1,31 @@
+use crate::{
+    board::board::Board, board::token::TokenColor, process::clock::Clock,
+    process::command::Command, process::report::SearchReport,
+};
+use std::time::Duration;
+
+pub trait Ui {
+    // Returns None once the input is exhausted, or when no command came within `limit`.
+    fn read_command(
+        &mut self,
+        board: &Board,
+        color: TokenColor,
+        limit: Option<Duration>,
+    ) -> Option<Command>;
+
+    fn show_board(&mut self, board: &Board, history: &[usize]);
+
+    // Called right after a token was dropped into the last column of `history`.
+    fn show_move(&mut self, board: &Board, history: &[usize]);
+
+    fn show_report(&mut self, report: &SearchReport);
+
+    fn show_clock(&mut self, clock: &Clock);
+
+    fn message(&mut self, text: &str);
+
+    // Waits for the user before continuing, returns false if they want to stop instead.
+    fn pause(&mut self, board: &Board, text: &str) -> bool;
+
+    fn game_over(&mut self, text: &str);
+}